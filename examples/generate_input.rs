@@ -0,0 +1,73 @@
+//! Writes a large, valid input for day 2 or day 5 to stdout, for benchmarking the parsers:
+//!
+//!     cargo run --release --example generate_input -- 5 3000000 > /tmp/5_large.txt
+//!
+//! The same day and line count always give the same input.
+
+use std::env;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+
+/// A xorshift generator, so that the inputs don't depend on a crate or the platform.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+}
+
+type LineWriter = fn(&mut dyn Write, &mut Random) -> io::Result<()>;
+
+fn write_movement(out: &mut dyn Write, random: &mut Random) -> io::Result<()> {
+    let direction = ["forward", "down", "up"][random.below(3) as usize];
+    writeln!(out, "{} {}", direction, random.below(9) + 1)
+}
+
+/// A horizontal, vertical or diagonal line inside a 1000x1000 plot.
+fn write_vent_line(out: &mut dyn Write, random: &mut Random) -> io::Result<()> {
+    let (x1, y1) = (random.below(1000), random.below(1000));
+    let (x2, y2) = match random.below(3) {
+        0 => (random.below(1000), y1),
+        1 => (x1, random.below(1000)),
+        _ => {
+            let length = random.below(1000 - x1.max(y1));
+            (x1 + length, y1 + length)
+        }
+    };
+    writeln!(out, "{},{} -> {},{}", x1, y1, x2, y2)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (write_line, lines): (LineWriter, _) = match args.as_slice() {
+        [day, lines] if day == "2" => (write_movement, lines),
+        [day, lines] if day == "5" => (write_vent_line, lines),
+        _ => {
+            eprintln!("Usage: generate_input <2|5> <lines>");
+            return ExitCode::from(2);
+        }
+    };
+    let lines: usize = match lines.parse() {
+        Ok(lines) => lines,
+        Err(e) => {
+            eprintln!("Invalid line count {:?}: {}", lines, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut random = Random(0x2021_1202);
+    let written = (0..lines).try_for_each(|_| write_line(&mut out, &mut random)).and_then(|_| out.flush());
+
+    match written {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Couldn't write the input: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::str::FromStr;
+use anyhow::{Context, Error, Result};
+
+pub const DEFAULT_ANSWERS_FILE: &str = "answers.txt";
+pub const EXAMPLE_ANSWERS_FILE: &str = "example_answers.txt";
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Verdict {
+    Correct,
+    Wrong(String),
+    Unknown,
+}
+
+impl Verdict {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Verdict::Correct => "correct",
+            Verdict::Wrong(_) => "wrong",
+            Verdict::Unknown => "unknown",
+        }
+    }
+
+    pub fn expected(&self) -> Option<&str> {
+        match self {
+            Verdict::Wrong(expected) => Some(expected),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Wrong(expected) => write!(f, "wrong, expected {}", expected),
+            v => write!(f, "{}", v.name()),
+        }
+    }
+}
+
+/// Known answers keyed by (day, part), read from a file with one `<day> <part> <answer>` entry per line.
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Answers {
+    expected: HashMap<(u32, u32), String>,
+}
+
+impl Answers {
+    pub fn load(path: &str) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => content.parse().with_context(|| format!("Couldn't read answers from {}", path)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Answers::default()),
+            Err(e) => Err(Error::from(e).context(format!("Couldn't open answers file {}", path))),
+        }
+    }
+
+    pub fn check(&self, day: u32, part: u32, answer: &str) -> Verdict {
+        match self.expected.get(&(day, part)) {
+            Some(expected) if expected == answer => Verdict::Correct,
+            Some(expected) => Verdict::Wrong(expected.clone()),
+            None => Verdict::Unknown,
+        }
+    }
+}
+
+impl FromStr for Answers {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut expected = HashMap::new();
+
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parts: Vec<_> = line.splitn(3, char::is_whitespace).collect();
+            if parts.len() != 3 {
+                return Err(Error::msg(format!("Line {}: expected `<day> <part> <answer>`", number + 1)));
+            }
+
+            let day = parts[0].parse().with_context(|| format!("Line {}: invalid day", number + 1))?;
+            let part = parts[1].parse().with_context(|| format!("Line {}: invalid part", number + 1))?;
+
+            if expected.insert((day, part), parts[2].trim().to_string()).is_some() {
+                return Err(Error::msg(format!("Line {}: duplicate answer for day {}, part {}", number + 1, day, part)));
+            }
+        }
+
+        Ok(Answers{expected})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_ANSWERS: &str = "# day part answer\n1 1 7\n\n1 2 5\n";
+
+    #[test]
+    fn test_parse_answers() {
+        let answers: Answers = EXAMPLE_ANSWERS.parse().unwrap();
+        assert_eq!(answers.expected.len(), 2);
+        assert_eq!(answers.expected[&(1, 2)], "5");
+    }
+
+    #[test]
+    fn test_check_answers() {
+        let answers: Answers = EXAMPLE_ANSWERS.parse().unwrap();
+        assert_eq!(answers.check(1, 1, "7"), Verdict::Correct);
+        assert_eq!(answers.check(1, 2, "6"), Verdict::Wrong("5".to_string()));
+        assert_eq!(answers.check(2, 1, "6"), Verdict::Unknown);
+    }
+
+    #[test]
+    fn test_invalid_answers() {
+        assert!("1 1".parse::<Answers>().is_err());
+        assert!("x 1 5".parse::<Answers>().is_err());
+        assert!("1 1 5\n1 1 6".parse::<Answers>().is_err());
+    }
+
+    #[test]
+    fn test_missing_file_has_no_answers() {
+        let answers = Answers::load("/nonexistent/answers.txt").unwrap();
+        assert_eq!(answers.check(1, 1, "7"), Verdict::Unknown);
+    }
+}
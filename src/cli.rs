@@ -0,0 +1,365 @@
+use std::ops::RangeInclusive;
+use anyhow::{Context, Error, Result};
+use crate::report::Format;
+
+pub const USAGE: &str = "\
+Usage: advent_of_code_2021 [OPTIONS] [-]
+       advent_of_code_2021 validate [OPTIONS] [-]
+       advent_of_code_2021 new-day <DAY> [TITLE...]
+
+Passing `-` reads the input of the selected day from stdin. `validate` parses the selected days'
+inputs without solving them, and reports their size and anything that would trip up a solver.
+`new-day` creates src/day<DAY>.rs from a template, empty input files, and registers the new module
+in main.rs.
+
+Options:
+    --day <DAYS>    Days to run, e.g. `4`, `3-5` or `1,3-5` (may be repeated)
+    --part <PART>   Only run the given part (1 or 2)
+    --all           Run every day, ignoring --day
+    --bench <N>     Run each exercise N times and report min/median/max timings
+    --jobs <N>      Run up to N days in parallel, or `auto` for one per CPU (default: 1)
+    --watch         Re-run whenever the selected days' inputs or examples change, and
+                    restart when the binary is rebuilt
+    --format <FMT>  Output format: `text` (default), `json` (JSON lines) or `csv`
+    --example       Run on the `{day}_example.txt` inputs and check against example_answers.txt
+    --lenient       Report every input line that doesn't parse, and solve with the lines that do
+    --stream        Solve the parts that support it line by line, without holding the input in memory
+    --answers <FILE>
+                    Check results against this answers file (default: answers.txt in the input folder)
+    --input-dir <DIR>
+                    Folder holding the puzzle inputs (default: $AOC_INPUT_DIR, then ./inputs of the checkout)
+    --input-pattern <PATTERN>
+                    Input file name, with `{day}` or `{day:02}` for the day (default: $AOC_INPUT_PATTERN, then `{day}.txt`).
+                    Examples are named the same with `_example` before the extension
+    --input-file <DAY>=<FILE>
+                    Read the given day's input from FILE (may be repeated)
+    --input-text <TEXT>
+                    Use TEXT as the input of the selected day
+    -h, --help      Print this message";
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub enum Command {
+    #[default]
+    Run,
+    Validate,
+    NewDay{day: u32, title: String},
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Options {
+    pub command: Command,
+    pub days: Vec<RangeInclusive<u32>>,
+    pub part: Option<u32>,
+    pub bench: Option<usize>,
+    pub jobs: usize,
+    pub format: Format,
+    pub answers: Option<String>,
+    pub input_dir: Option<String>,
+    pub input_pattern: Option<String>,
+    pub input_files: Vec<(u32, String)>,
+    pub input_text: Option<String>,
+    pub stdin: bool,
+    pub watch: bool,
+    pub example: bool,
+    pub lenient: bool,
+    pub stream: bool,
+    pub help: bool,
+}
+
+impl Options {
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut options = Options{jobs: 1, ..Options::default()};
+        let mut all = false;
+        let mut args = args.into_iter().peekable();
+
+        if args.peek().map(String::as_str) == Some("new-day") {
+            args.next();
+            let day = args.next().ok_or_else(|| Error::msg("Missing day for new-day"))?;
+            let day = parse_day(&day)?;
+            let title = args.collect::<Vec<_>>().join(" ");
+
+            options.command = Command::NewDay{day, title};
+            return Ok(options);
+        }
+
+        if args.peek().map(String::as_str) == Some("validate") {
+            args.next();
+            options.command = Command::Validate;
+        }
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg, None),
+            };
+
+            let mut value = || inline_value.clone().or_else(|| args.next())
+                .ok_or_else(|| Error::msg(format!("Missing value for {}", flag)));
+
+            match flag.as_str() {
+                "--day" => options.days.extend(parse_day_ranges(&value()?)?),
+                "--part" => options.part = Some(parse_part(&value()?)?),
+                "--all" => all = true,
+                "--bench" => options.bench = Some(parse_repetitions(&value()?)?),
+                "--jobs" => options.jobs = parse_jobs(&value()?)?,
+                "--format" => options.format = value()?.parse()?,
+                "--answers" => options.answers = Some(value()?),
+                "--input-dir" => options.input_dir = Some(value()?),
+                "--input-pattern" => options.input_pattern = Some(value()?),
+                "--input-file" => options.input_files.push(parse_input_file(&value()?)?),
+                "--input-text" => options.input_text = Some(value()?),
+                "-" => options.stdin = true,
+                "--watch" => options.watch = true,
+                "--example" => options.example = true,
+                "--lenient" => options.lenient = true,
+                "--stream" => options.stream = true,
+                "-h" | "--help" => options.help = true,
+                _ => return Err(Error::msg(format!("Unknown argument: {}", flag))),
+            }
+        }
+
+        if all {
+            options.days.clear();
+        }
+
+        if options.stdin && options.input_text.is_some() {
+            return Err(Error::msg("Can't read input from both stdin and --input-text"));
+        }
+
+        if options.example && (options.stdin || options.input_text.is_some()) {
+            return Err(Error::msg("Can't use --example with input from stdin or --input-text"));
+        }
+
+        if options.command == Command::Validate && (options.watch || options.bench.is_some()) {
+            return Err(Error::msg("Can't use --watch or --bench with validate"));
+        }
+
+        if options.lenient && options.stream {
+            return Err(Error::msg("Can't use --lenient with --stream"));
+        }
+
+        if options.stdin && options.watch {
+            return Err(Error::msg("Can't watch input read from stdin"));
+        }
+
+        if (options.stdin || options.input_text.is_some()) && options.single_day().is_none() {
+            return Err(Error::msg("Reading input from stdin or --input-text needs a single --day"));
+        }
+
+        Ok(options)
+    }
+
+    pub fn single_day(&self) -> Option<u32> {
+        match self.days.as_slice() {
+            [range] if range.start() == range.end() => Some(*range.start()),
+            _ => None,
+        }
+    }
+
+    pub fn selects(&self, day: u32, part: u32) -> bool {
+        let day_selected = self.days.is_empty() || self.days.iter().any(|r| r.contains(&day));
+        let part_selected = self.part.is_none_or(|p| p == part);
+
+        day_selected && part_selected
+    }
+}
+
+fn parse_day_ranges(s: &str) -> Result<Vec<RangeInclusive<u32>>> {
+    s.split(',').map(parse_day_range).collect()
+}
+
+fn parse_day(s: &str) -> Result<u32> {
+    parse_day_range(s).ok()
+        .filter(|r| r.start() == r.end())
+        .map(|r| *r.start())
+        .ok_or_else(|| Error::msg(format!("Invalid day: {}", s)))
+}
+
+fn parse_day_range(s: &str) -> Result<RangeInclusive<u32>> {
+    let s = s.trim();
+    let (start, end) = match s.split_once('-') {
+        Some((start, end)) => (start, end),
+        None => (s, s),
+    };
+
+    let start: u32 = start.trim().parse().with_context(|| format!("Invalid day: {}", s))?;
+    let end: u32 = end.trim().parse().with_context(|| format!("Invalid day: {}", s))?;
+
+    if start == 0 || start > end || end > 25 {
+        return Err(Error::msg(format!("Invalid day range: {}", s)));
+    }
+
+    Ok(start..=end)
+}
+
+fn parse_part(s: &str) -> Result<u32> {
+    match s.trim() {
+        "1" => Ok(1),
+        "2" => Ok(2),
+        _ => Err(Error::msg(format!("Invalid part: {}", s))),
+    }
+}
+
+fn parse_repetitions(s: &str) -> Result<usize> {
+    match s.trim().parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(Error::msg(format!("Invalid number of repetitions: {}", s))),
+    }
+}
+
+fn parse_jobs(s: &str) -> Result<usize> {
+    match s.trim() {
+        "auto" => Ok(std::thread::available_parallelism().map_or(1, |n| n.get())),
+        n => match n.parse() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(Error::msg(format!("Invalid number of jobs: {}", s))),
+        },
+    }
+}
+
+fn parse_input_file(s: &str) -> Result<(u32, String)> {
+    let (day, path) = s.split_once('=')
+        .ok_or_else(|| Error::msg(format!("Expected <DAY>=<FILE>, got: {}", s)))?;
+    Ok((parse_day(day)?, path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        Options::from_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_no_arguments_selects_everything() {
+        let options = parse(&[]).unwrap();
+        assert!(options.selects(1, 1));
+        assert!(options.selects(5, 2));
+    }
+
+    #[test]
+    fn test_day_ranges() {
+        let options = parse(&["--day", "1,3-4", "--day=5"]).unwrap();
+        assert_eq!(options.days, vec![1..=1, 3..=4, 5..=5]);
+        assert!(!options.selects(2, 1));
+        assert!(options.selects(4, 2));
+    }
+
+    #[test]
+    fn test_part_selection() {
+        let options = parse(&["--day", "4", "--part", "2"]).unwrap();
+        assert!(options.selects(4, 2));
+        assert!(!options.selects(4, 1));
+        assert!(!options.selects(3, 2));
+    }
+
+    #[test]
+    fn test_all_overrides_days() {
+        let options = parse(&["--day", "4", "--all"]).unwrap();
+        assert!(options.selects(1, 1));
+    }
+
+    #[test]
+    fn test_bench_repetitions() {
+        assert_eq!(parse(&["--bench", "10"]).unwrap().bench, Some(10));
+        assert_eq!(parse(&[]).unwrap().bench, None);
+        assert!(parse(&["--bench", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_jobs() {
+        assert_eq!(parse(&[]).unwrap().jobs, 1);
+        assert_eq!(parse(&["--jobs", "4"]).unwrap().jobs, 4);
+        assert!(parse(&["--jobs", "auto"]).unwrap().jobs >= 1);
+        assert!(parse(&["--jobs", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_output_format() {
+        assert_eq!(parse(&[]).unwrap().format, Format::Text);
+        assert_eq!(parse(&["--format", "csv"]).unwrap().format, Format::Csv);
+        assert!(parse(&["--format", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn test_input_location() {
+        let options = parse(&["--input-dir", "/data", "--input-file", "4=/tmp/4.txt", "--input-pattern={day:02}.in"]).unwrap();
+        assert_eq!(options.input_dir.as_deref(), Some("/data"));
+        assert_eq!(options.input_pattern.as_deref(), Some("{day:02}.in"));
+        assert_eq!(options.input_files, vec![(4, "/tmp/4.txt".to_string())]);
+        assert!(parse(&["--input-file", "4"]).is_err());
+        assert!(parse(&["--input-file", "0=/tmp/0.txt"]).is_err());
+        assert!(parse(&["--input-file", "26=/tmp/26.txt"]).is_err());
+        assert!(parse(&["--input-file", "3-4=/tmp/3.txt"]).is_err());
+    }
+
+    #[test]
+    fn test_inline_input_needs_single_day() {
+        let options = parse(&["--day", "4", "-"]).unwrap();
+        assert!(options.stdin);
+        assert_eq!(options.single_day(), Some(4));
+
+        assert!(parse(&["--day", "4", "--input-text", "1\n2"]).is_ok());
+        assert!(parse(&["--day", "3-4", "-"]).is_err());
+        assert!(parse(&["--input-text", "1"]).is_err());
+        assert!(parse(&["--day", "4", "-", "--input-text", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_watch() {
+        assert!(parse(&["--day", "4", "--watch"]).unwrap().watch);
+        assert!(parse(&["--day", "4", "--watch", "-"]).is_err());
+    }
+
+    #[test]
+    fn test_new_day_command() {
+        let options = parse(&["new-day", "6", "Lanternfish"]).unwrap();
+        assert_eq!(options.command, Command::NewDay{day: 6, title: "Lanternfish".to_string()});
+
+        let options = parse(&["new-day", "7", "The", "Treachery", "of", "Whales"]).unwrap();
+        assert_eq!(options.command, Command::NewDay{day: 7, title: "The Treachery of Whales".to_string()});
+
+        assert!(parse(&["new-day"]).is_err());
+        assert!(parse(&["new-day", "26"]).is_err());
+        assert_eq!(parse(&[]).unwrap().command, Command::Run);
+    }
+
+    #[test]
+    fn test_validate_command() {
+        let options = parse(&["validate", "--day", "5", "--example"]).unwrap();
+        assert_eq!(options.command, Command::Validate);
+        assert_eq!(options.days, vec![5..=5]);
+        assert!(options.example);
+
+        assert!(parse(&["validate", "--bench", "3"]).is_err());
+        assert!(parse(&["--day", "5", "validate"]).is_err());
+    }
+
+    #[test]
+    fn test_example_mode() {
+        assert!(parse(&["--example"]).unwrap().example);
+        assert!(parse(&["--day", "4", "--example", "-"]).is_err());
+    }
+
+    #[test]
+    fn test_lenient() {
+        assert!(parse(&["--day", "5", "--lenient"]).unwrap().lenient);
+        assert!(!parse(&[]).unwrap().lenient);
+    }
+
+    #[test]
+    fn test_stream() {
+        assert!(parse(&["--day", "1", "--stream"]).unwrap().stream);
+        assert!(parse(&["--stream", "--lenient"]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert!(parse(&["--day", "5-3"]).is_err());
+        assert!(parse(&["--day", "x"]).is_err());
+        assert!(parse(&["--part", "3"]).is_err());
+        assert!(parse(&["--day"]).is_err());
+        assert!(parse(&["--frobnicate"]).is_err());
+    }
+}
@@ -1,49 +1,101 @@
-use anyhow::Result;
-use crate::input_handling::parse_file_line_by_line;
+use std::collections::VecDeque;
+use std::io::BufRead;
+use crate::error::{PuzzleError, Result};
+use crate::input_handling::{parsed_lines, parse_line_by_line, validate_line_by_line, LineNumbers};
+use crate::solution::{Answer, Inspection, Solution, StreamedSolver};
 
-pub fn part1(input_path: &str) -> Result<i32> {
-    let input = parse_file_line_by_line(input_path)?;
-    Ok(check_increases_in_sliding_windows(&input, 1))
-}
+pub struct SonarSweep;
 
-pub fn part2(input_path: &str) -> Result<i32> {
-    let input = parse_file_line_by_line(input_path)?;
-    Ok(check_increases_in_sliding_windows(&input, 3))
-}
+impl Solution for SonarSweep {
+    const DAY: u32 = 1;
+    const TITLE: &'static str = "Sonar Sweep";
 
-fn check_increases_in_sliding_windows(data: &[i32], window_size: usize) -> i32 {
-    let mut result = 0;
-    let mut previous = None;
+    type Input = Vec<i32>;
+
+    fn parse<R: BufRead>(reader: R) -> Result<Self::Input> {
+        parse_line_by_line(reader)
+    }
+
+    fn parse_leniently<R: BufRead>(reader: R) -> Result<(Self::Input, Vec<PuzzleError>)> {
+        validate_line_by_line(reader)
+    }
 
-    for window in data.windows(window_size) {
-        if window.len() < window_size {
-            break;
+    fn inspect(input: &Self::Input, _lines: &LineNumbers) -> Inspection {
+        let mut inspection = Inspection::default();
+        inspection.figure("depths", input.len());
+
+        match (input.iter().min(), input.iter().max()) {
+            (Some(min), Some(max)) => inspection.figure("range", format!("{}..={}", min, max)),
+            _ => inspection.issue("Input is empty"),
         }
+        inspection
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        Ok(check_increases_in_sliding_windows(input, 1).into())
+    }
 
-        let current_sum: i32 = window.iter().sum();
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        Ok(check_increases_in_sliding_windows(input, 3).into())
+    }
 
-        if let Some(previous_sum) = previous {
-            if current_sum > previous_sum {
-                result += 1;
-            }
+    fn streamed_solver(part: u32) -> Option<StreamedSolver> {
+        match part {
+            1 => Some(|reader| Ok(count_increases_in_reader(reader, 1)?.into())),
+            2 => Some(|reader| Ok(count_increases_in_reader(reader, 3)?.into())),
+            _ => None,
         }
+    }
+}
+
+fn check_increases_in_sliding_windows(data: &[i32], window_size: usize) -> i32 {
+    count_increases(data.iter().copied(), window_size)
+}
+
+/// Counts the sliding windows whose sum is larger than the previous window's, keeping only the
+/// current window in memory.
+fn count_increases<I: IntoIterator<Item = i32>>(depths: I, window_size: usize) -> i32 {
+    let mut window = VecDeque::with_capacity(window_size + 1);
+    let mut result = 0;
 
-        previous = Some(current_sum);
+    for depth in depths {
+        window.push_back(depth);
+
+        // Neighbouring windows share all but the oldest and the newest depth.
+        if window.len() > window_size && window.pop_front().is_some_and(|oldest| depth > oldest) {
+            result += 1;
+        }
     }
 
     result
 }
 
+/// Counts the increases as the depths are read.
+fn count_increases_in_reader<R: BufRead>(reader: R, window_size: usize) -> Result<i32> {
+    itertools::process_results(parsed_lines(reader), |depths| count_increases(depths, window_size))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::input_handling::example_input;
     use super::*;
 
+    const EXAMPLE: &str = "\
+199
+200
+208
+210
+200
+207
+240
+269
+260
+263";
+
     const EXAMPLE_INPUT: [i32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
 
     #[test]
     fn test_parse_input() {
-        let input: Vec<i32> = parse_file_line_by_line(&example_input(1)).unwrap();
+        let input: Vec<i32> = parse_line_by_line(EXAMPLE.as_bytes()).unwrap();
         assert_eq!(input.as_slice(), &EXAMPLE_INPUT);
     }
 
@@ -52,6 +104,21 @@ mod tests {
         assert_eq!(check_increases_in_sliding_windows(&EXAMPLE_INPUT, 1), 7);
     }
 
+    #[test]
+    fn test_count_increases_in_reader() {
+        assert_eq!(count_increases_in_reader(EXAMPLE.as_bytes(), 1).unwrap(), 7);
+        assert_eq!(count_increases_in_reader(EXAMPLE.as_bytes(), 3).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_inspect() {
+        let inspection = SonarSweep::inspect(&EXAMPLE_INPUT.to_vec(), &LineNumbers::default());
+        assert_eq!(inspection.figures, vec![("depths", "10".to_string()), ("range", "199..=269".to_string())]);
+        assert!(inspection.issues.is_empty());
+
+        assert_eq!(SonarSweep::inspect(&vec![], &LineNumbers::default()).issues, vec!["Input is empty"]);
+    }
+
     #[test]
     fn test_part2() {
         assert_eq!(check_increases_in_sliding_windows(&EXAMPLE_INPUT, 3), 5);
@@ -1,41 +1,79 @@
 use std::str::FromStr;
-use anyhow::Result;
-use crate::input_handling::parse_file_line_by_line;
+use std::io::BufRead;
+use crate::error::{ParseError, PuzzleError, Result};
+use crate::input_handling::{parse_line_by_line, validate_line_by_line, LineNumbers, Pattern};
+use crate::solution::{Answer, Inspection, Solution};
+
+pub struct Dive;
+
+impl Solution for Dive {
+    const DAY: u32 = 2;
+    const TITLE: &'static str = "Dive!";
+
+    type Input = Vec<Movement>;
+
+    fn parse<R: BufRead>(reader: R) -> Result<Self::Input> {
+        parse_line_by_line(reader)
+    }
+
+    fn parse_leniently<R: BufRead>(reader: R) -> Result<(Self::Input, Vec<PuzzleError>)> {
+        validate_line_by_line(reader)
+    }
+
+    fn inspect(input: &Self::Input, _lines: &LineNumbers) -> Inspection {
+        let mut inspection = Inspection::default();
+        inspection.figure("movements", input.len());
+
+        match input.iter().map(Movement::amount).max() {
+            Some(max) => inspection.figure("max amount", max),
+            None => inspection.issue("Input is empty"),
+        }
+        inspection
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        Ok(move_directly(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        Ok(move_with_aim(input).into())
+    }
+}
 
 #[derive(Debug, Eq, PartialEq)]
-enum Movement {
-    Forward(i32),
-    Down(i32),
-    Up(i32),
+pub enum Movement {
+    Forward(i64),
+    Down(i64),
+    Up(i64),
 }
 
+impl Movement {
+    fn amount(&self) -> i64 {
+        match self {
+            Movement::Forward(n) | Movement::Down(n) | Movement::Up(n) => *n,
+        }
+    }
+}
+
+const MOVEMENT: Pattern = Pattern::new("{direction} {amount}");
+
 impl FromStr for Movement {
-    type Err = &'static str;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split(' ').into_iter().collect();
-        if parts.len() != 2 {
-            return Err("Wrong number of parts")
-        }
+        let fields = MOVEMENT.captures(s)?;
+        let amount = fields.get("amount")?;
 
-        let direction = parts[0];
-        let amount = parts[1].parse().or(Err("Amount is not a number"))?;
-
-        match direction {
+        match fields.text("direction") {
             "forward" => Ok(Movement::Forward(amount)),
             "down" => Ok(Movement::Down(amount)),
             "up" => Ok(Movement::Up(amount)),
-            _ => return Err("Invalid amount"),
+            _ => Err(ParseError::at(fields.column("direction"), "Unknown direction")),
         }
     }
 }
 
-pub fn part1(input_path: &str) -> Result<i32> {
-    let input = parse_file_line_by_line(input_path)?;
-    Ok(move_directly(&input))
-}
-
-fn move_directly(input: &[Movement]) -> i32 {
+fn move_directly(input: &[Movement]) -> i64 {
     let mut horizontal_distance = 0;
     let mut depth = 0;
 
@@ -50,12 +88,7 @@ fn move_directly(input: &[Movement]) -> i32 {
     horizontal_distance * depth
 }
 
-pub fn part2(input_path: &str) -> Result<i32> {
-    let input = parse_file_line_by_line(input_path)?;
-    Ok(move_with_aim(&input))
-}
-
-fn move_with_aim(input: &[Movement]) -> i32 {
+fn move_with_aim(input: &[Movement]) -> i64 {
     let mut horizontal_distance = 0;
     let mut depth = 0;
     let mut aim = 0;
@@ -76,18 +109,32 @@ fn move_with_aim(input: &[Movement]) -> i32 {
 
 #[cfg(test)]
 mod tests {
-    use crate::input_handling::example_input;
     use super::*;
     use Movement::*;
 
+    const EXAMPLE: &str = "\
+forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2";
+
     const EXAMPLE_INPUT: [Movement; 6] = [Forward(5), Down(5), Forward(8), Up(3), Down(8), Forward(2)];
 
     #[test]
     fn test_parse_input() {
-        let input: Vec<Movement> = parse_file_line_by_line(&example_input(2)).unwrap();
+        let input: Vec<Movement> = parse_line_by_line(EXAMPLE.as_bytes()).unwrap();
         assert_eq!(input.as_slice(), &EXAMPLE_INPUT);
     }
 
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!("sideways 3".parse::<Movement>().unwrap_err().column, Some(1));
+        assert_eq!("up x".parse::<Movement>().unwrap_err().column, Some(4));
+        assert_eq!("up".parse::<Movement>().unwrap_err().to_string(), "Expected \" \" after direction");
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(move_directly(&EXAMPLE_INPUT), 150);
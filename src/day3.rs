@@ -1,9 +1,78 @@
 use std::str::FromStr;
-use anyhow::Result;
-use crate::input_handling::parse_file_line_by_line;
+use std::io::BufRead;
+use crate::error::{ParseError, PuzzleError, Result};
+use crate::input_handling::{parsed_lines, parse_line_by_line, validate_line_by_line, LineNumbers};
+use crate::solution::{Answer, Inspection, Solution, StreamedSolver};
+
+pub struct BinaryDiagnostic;
+
+impl Solution for BinaryDiagnostic {
+    const DAY: u32 = 3;
+    const TITLE: &'static str = "Binary Diagnostic";
+
+    type Input = Vec<SubmarineDiagnosticLine>;
+
+    fn parse<R: BufRead>(reader: R) -> Result<Self::Input> {
+        parse_line_by_line(reader)
+    }
+
+    fn parse_leniently<R: BufRead>(reader: R) -> Result<(Self::Input, Vec<PuzzleError>)> {
+        validate_line_by_line(reader)
+    }
+
+    fn inspect(input: &Self::Input, lines: &LineNumbers) -> Inspection {
+        let mut inspection = Inspection::default();
+        inspection.figure("lines", input.len());
+
+        let width = match input.first() {
+            Some(first) => first.bits.len(),
+            None => {
+                inspection.issue("Input is empty");
+                return inspection;
+            }
+        };
+        inspection.figure("bit width", width);
+
+        let mixed: Vec<_> = input.iter().enumerate()
+            .filter(|(_, line)| line.bits.len() != width)
+            .map(|(i, line)| format!("{} ({} bits)", lines.of(i), line.bits.len()))
+            .collect();
+        if !mixed.is_empty() {
+            inspection.issue(format!("Not {} bits wide like the first line: lines {}", width, mixed.join(", ")));
+        }
+        if width == 0 || width > MAX_BITS {
+            inspection.issue(format!("Lines are {} bits wide, but must be 1 to {}", width, MAX_BITS));
+        }
+        inspection
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        check_widths(input)?;
+        Ok(part1_impl(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        check_widths(input)?;
+        Ok(part2_impl(input)?.into())
+    }
+
+    /// Part 2 keeps filtering the lines, so only part 1 can be streamed.
+    fn streamed_solver(part: u32) -> Option<StreamedSolver> {
+        match part {
+            1 => Some(|reader| {
+                let (gamma, epsilon) = gamma_epsilon_rates_in_reader(reader)?;
+                Ok((gamma as u128 * epsilon as u128).into())
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The most bits a line can have and still fit in a rate.
+const MAX_BITS: usize = u64::BITS as usize;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
-struct SubmarineDiagnosticLine {
+pub struct SubmarineDiagnosticLine {
     bits: Vec<bool>
 }
 
@@ -13,7 +82,7 @@ impl SubmarineDiagnosticLine {
         SubmarineDiagnosticLine{bits: bits.into()}
     }
 
-    fn to_decimal(&self) -> i32 {
+    fn to_decimal(&self) -> u64 {
         self.bits.iter().rev()
             .enumerate()
             .filter_map(|(i, &b)| if b { Some(1 << i) } else { None })
@@ -22,17 +91,15 @@ impl SubmarineDiagnosticLine {
 }
 
 impl FromStr for SubmarineDiagnosticLine {
-    type Err = &'static str;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.trim();
-
         let mut bits = vec![];
         for (i, c) in s.chars().enumerate() {
             match c {
                 '0' => bits.push(false),
                 '1' => bits.push(true),
-                _ => return Err("Encountered invalid character"),
+                _ => return Err(ParseError::at(i + 1, "Encountered invalid character")),
             }
         }
 
@@ -40,81 +107,118 @@ impl FromStr for SubmarineDiagnosticLine {
     }
 }
 
-pub fn part1(input_path: &str) -> Result<i32> {
-    let input = parse_file_line_by_line(input_path)?;
-    Ok(part1_impl(&input))
+/// Checks that there are lines, all of the same width, and that the rates fit in a `u64`.
+fn check_widths(input: &[SubmarineDiagnosticLine]) -> Result<usize> {
+    let width = input.first()
+        .ok_or_else(|| PuzzleError::invalid_data("Empty input"))?
+        .bits.len();
+
+    for (i, line) in input.iter().enumerate() {
+        check_width(i + 1, line, width)?;
+    }
+    Ok(width)
+}
+
+fn check_width(line_number: usize, line: &SubmarineDiagnosticLine, width: usize) -> Result<()> {
+    if line.bits.len() != width {
+        return Err(PuzzleError::invalid_data(format!("Line {} is {} bits wide, but the first line is {}", line_number, line.bits.len(), width)));
+    }
+    if width == 0 || width > MAX_BITS {
+        return Err(PuzzleError::invalid_data(format!("Lines are {} bits wide, but must be 1 to {}", width, MAX_BITS)));
+    }
+    Ok(())
 }
 
-fn part1_impl(input: &[SubmarineDiagnosticLine]) -> i32 {
+fn part1_impl(input: &[SubmarineDiagnosticLine]) -> u128 {
     let (gamma, epsilon) = calculate_gamma_epsilon_rates(input);
-    gamma * epsilon
+    gamma as u128 * epsilon as u128
 }
 
-fn calculate_gamma_epsilon_rates(input: &[SubmarineDiagnosticLine]) -> (i32, i32) {
-    let mut ones_in_position = count_ones_in_each_position(input);
-    let majority_count = input.len() / 2;
+fn calculate_gamma_epsilon_rates(input: &[SubmarineDiagnosticLine]) -> (u64, u64) {
+    rates_from_counts(&count_ones_in_each_position(input), input.len())
+}
+
+/// Part 1's rates counted line by line as they're read, without keeping the lines around.
+fn gamma_epsilon_rates_in_reader<R: BufRead>(reader: R) -> Result<(u64, u64)> {
+    let mut ones_in_position = vec![];
+    let mut lines = 0;
+    let mut width = None;
+
+    for line in parsed_lines(reader) {
+        let line = line?;
+        lines += 1;
+        check_width(lines, &line, *width.get_or_insert(line.bits.len()))?;
+        add_ones(&mut ones_in_position, &line);
+    }
+
+    if lines == 0 {
+        return Err(PuzzleError::invalid_data("Empty input"));
+    }
+
+    Ok(rates_from_counts(&ones_in_position, lines))
+}
+
+fn rates_from_counts(ones_in_position: &[usize], lines: usize) -> (u64, u64) {
+    let majority_count = lines / 2;
 
     let gamma_bits = ones_in_position.iter().map(|&c| if c > majority_count { 1 } else { 0 });
-    let gamma: i32 = gamma_bits.rev().enumerate().map(|(i, b)| (1 << i) * b).sum();
+    let gamma: u64 = gamma_bits.rev().enumerate().map(|(i, b)| (1 << i) * b).sum();
 
     let number_bits = ones_in_position.len();
-    let mask = (1 << (number_bits)) - 1 ;
+    let mask = u64::MAX >> (MAX_BITS - number_bits);
     let epsilon = !gamma & mask;
 
     (gamma, epsilon)
 }
 
 fn count_ones_in_each_position(input: &[SubmarineDiagnosticLine]) -> Vec<usize> {
-    let max_bits = input.iter().map(|l| l.bits.len()).max().expect("Empty input");
-    let mut ones_in_position = vec![0; max_bits];
+    let mut ones_in_position = vec![];
 
     for line in input {
-        for (p, &bit) in line.bits.iter().enumerate() {
-            if bit {
-                ones_in_position[p] += 1;
-            }
-        }
+        add_ones(&mut ones_in_position, line);
     }
 
     ones_in_position
 }
 
-pub fn part2(input_path: &str) -> Result<i32> {
-    let input = parse_file_line_by_line(input_path)?;
-    Ok(part2_impl(&input))
+fn add_ones(ones_in_position: &mut Vec<usize>, line: &SubmarineDiagnosticLine) {
+    if ones_in_position.len() < line.bits.len() {
+        ones_in_position.resize(line.bits.len(), 0);
+    }
+
+    for (p, &bit) in line.bits.iter().enumerate() {
+        if bit {
+            ones_in_position[p] += 1;
+        }
+    }
 }
 
-fn part2_impl(input: &[SubmarineDiagnosticLine]) -> i32 {
-    let (oxygen_rate, co2_rate) = calculate_oxygen_and_co2_rates(input);
-    oxygen_rate * co2_rate
+fn part2_impl(input: &[SubmarineDiagnosticLine]) -> Result<u128> {
+    let (oxygen_rate, co2_rate) = calculate_oxygen_and_co2_rates(input)?;
+    Ok(oxygen_rate as u128 * co2_rate as u128)
 }
 
-fn calculate_oxygen_and_co2_rates(input: &[SubmarineDiagnosticLine]) -> (i32, i32) {
-    let max_bits = input.iter().map(|l| l.bits.len()).max().expect("Empty input");
-    let oxygen_rate = filter_gas_rate(input, true);
-    let co2_rate = filter_gas_rate(input, false);
-    (oxygen_rate, co2_rate)
+fn calculate_oxygen_and_co2_rates(input: &[SubmarineDiagnosticLine]) -> Result<(u64, u64)> {
+    let oxygen_rate = filter_gas_rate(input, true)?;
+    let co2_rate = filter_gas_rate(input, false)?;
+    Ok((oxygen_rate, co2_rate))
 }
 
-fn filter_gas_rate(input: &[SubmarineDiagnosticLine], oxygen: bool) -> i32 {
-    let max_bits = input.iter().map(|l| l.bits.len()).max().expect("Empty input");
+fn filter_gas_rate(input: &[SubmarineDiagnosticLine], oxygen: bool) -> Result<u64> {
+    let max_bits = check_widths(input)?;
 
-    let mut gas_rate: Vec<_> = input.iter().cloned().collect();
+    let mut gas_rate: Vec<_> = input.to_vec();
     for i in 0..max_bits {
         if gas_rate.len() == 1 {
             break;
         }
 
         if gas_rate.is_empty() {
-            panic!("Wrong!");
+            return Err(PuzzleError::no_solution("No number matches the bit criteria"));
         }
 
         let counters = count_ones_in_each_position(&gas_rate);
-        let majority_count = if gas_rate.len() % 2 == 0 {
-            gas_rate.len() / 2
-        } else {
-            1 + gas_rate.len() / 2
-        };
+        let majority_count = gas_rate.len().div_ceil(2);
 
         let filter_by = if counters[i] >= majority_count {
             false ^ oxygen
@@ -125,25 +229,57 @@ fn filter_gas_rate(input: &[SubmarineDiagnosticLine], oxygen: bool) -> i32 {
         gas_rate.retain(|e| e.bits[i] == filter_by);
     }
 
-    gas_rate[0].to_decimal()
+    match gas_rate.as_slice() {
+        [rate] => Ok(rate.to_decimal()),
+        _ => Err(PuzzleError::no_solution("More than one number matches the bit criteria")),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::input_handling::example_input;
+    use crate::error::ErrorKind;
     use super::*;
 
+    const EXAMPLE: &str = "\
+00100
+11110
+10110
+10111
+10101
+01111
+00111
+11100
+10000
+11001
+00010
+01010";
+
     #[test]
     fn test_parse_input() {
-        let input: Vec<SubmarineDiagnosticLine> = parse_file_line_by_line(&example_input(3)).unwrap();
+        let input: Vec<SubmarineDiagnosticLine> = parse_line_by_line(EXAMPLE.as_bytes()).unwrap();
         assert_eq!(input.as_slice(), &create_example_input());
     }
 
+    #[test]
+    fn test_invalid_character_has_column() {
+        assert_eq!("0120".parse::<SubmarineDiagnosticLine>().unwrap_err().column, Some(3));
+    }
+
+    #[test]
+    fn test_empty_input_is_invalid() {
+        assert_eq!(BinaryDiagnostic::part1(&vec![]).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
     #[test]
     fn test_calculate_gamma_epsilon_rates() {
         assert_eq!(calculate_gamma_epsilon_rates(&create_example_input()), (22, 9));
     }
 
+    #[test]
+    fn test_gamma_epsilon_rates_in_reader() {
+        assert_eq!(gamma_epsilon_rates_in_reader(EXAMPLE.as_bytes()).unwrap(), (22, 9));
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(part1_impl(&create_example_input()), 198);
@@ -151,7 +287,7 @@ mod tests {
 
     #[test]
     fn test_calculate_oxygen_co2_rates() {
-        assert_eq!(calculate_oxygen_and_co2_rates(&create_example_input()), (23, 10));
+        assert_eq!(calculate_oxygen_and_co2_rates(&create_example_input()).unwrap(), (23, 10));
     }
 
     fn create_example_input() -> Vec<SubmarineDiagnosticLine> {
@@ -171,8 +307,48 @@ mod tests {
         ]
     }
 
+    #[test]
+    fn test_inspect_counts_skipped_lines() {
+        let (input, skipped) = BinaryDiagnostic::parse_leniently("0x1\n0101\n01\n".as_bytes()).unwrap();
+        let inspection = BinaryDiagnostic::inspect(&input, &LineNumbers::skipping(&skipped));
+
+        assert_eq!(inspection.issues, vec!["Not 4 bits wide like the first line: lines 3 (2 bits)"]);
+    }
+
+    #[test]
+    fn test_inspect_mixed_widths() {
+        let mut input = create_example_input();
+        assert!(BinaryDiagnostic::inspect(&input, &LineNumbers::default()).issues.is_empty());
+
+        input[3] = SubmarineDiagnosticLine::new(vec![true, false, true]);
+        let inspection = BinaryDiagnostic::inspect(&input, &LineNumbers::default());
+        assert_eq!(inspection.figures, vec![("lines", "12".to_string()), ("bit width", "5".to_string())]);
+        assert_eq!(inspection.issues, vec!["Not 5 bits wide like the first line: lines 4 (3 bits)"]);
+    }
+
+    #[test]
+    fn test_mixed_widths_are_invalid() {
+        let input: Vec<SubmarineDiagnosticLine> = parse_line_by_line("1111\n11\n".as_bytes()).unwrap();
+
+        assert_eq!(BinaryDiagnostic::part1(&input).unwrap_err().to_string(), "Line 2 is 2 bits wide, but the first line is 4");
+        assert_eq!(BinaryDiagnostic::part2(&input).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(gamma_epsilon_rates_in_reader("1111\n11\n".as_bytes()).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_widths_up_to_64_bits() {
+        let input = vec![SubmarineDiagnosticLine::new(vec![true; 64]), SubmarineDiagnosticLine::new([&[false][..], &[true; 63]].concat())];
+        let low_bits = u64::MAX as u128 >> 1;
+
+        assert_eq!(BinaryDiagnostic::part1(&input).unwrap(), Answer::Wide(low_bits * (1 << 63)));
+        assert_eq!(BinaryDiagnostic::part2(&input).unwrap(), Answer::Wide(u64::MAX as u128 * low_bits));
+
+        let too_wide: Vec<SubmarineDiagnosticLine> = parse_line_by_line("1".repeat(65).as_bytes()).unwrap();
+        assert_eq!(BinaryDiagnostic::part1(&too_wide).unwrap_err().to_string(), "Lines are 65 bits wide, but must be 1 to 64");
+    }
+
     #[test]
     fn test_part2() {
-        assert_eq!(part2_impl(&create_example_input()), 230);
+        assert_eq!(part2_impl(&create_example_input()).unwrap(), 230);
     }
 }
\ No newline at end of file
@@ -1,13 +1,59 @@
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
-use std::fs::File;
-use std::io::{prelude::BufRead, BufReader};
-use std::str::FromStr;
-use anyhow::{Context, Result};
-use itertools::Itertools;
+use std::io::prelude::BufRead;
+use crate::error::{ParseError, PuzzleError, Result};
 use crate::day4::BoardNumber::{Drawn, NotDrawn};
+use crate::input_handling::{parse_sections, Grid, LineNumbers, Section};
+use crate::solution::{Answer, Inspection, Solution};
+
+pub struct GiantSquid;
+
+impl Solution for GiantSquid {
+    const DAY: u32 = 4;
+    const TITLE: &'static str = "Giant Squid";
+
+    type Input = Bingo;
+
+    fn parse<R: BufRead>(reader: R) -> Result<Self::Input> {
+        parse_input(reader)
+    }
+
+    fn inspect(input: &Self::Input, _lines: &LineNumbers) -> Inspection {
+        let mut inspection = Inspection::default();
+        inspection.figure("draw length", input.draw_order.len());
+        inspection.figure("boards", input.boards.len());
+
+        let repeated = repeated_numbers(input.draw_order.iter().copied());
+        if !repeated.is_empty() {
+            inspection.issue(format!("Draw order repeats {}", join_numbers(&repeated)));
+        }
+
+        for (i, board) in input.boards.iter().enumerate() {
+            let repeated = repeated_numbers(board.numbers.iter().map(BoardNumber::value));
+            if !repeated.is_empty() {
+                inspection.issue(format!("Board {} repeats {}", i + 1, join_numbers(&repeated)));
+            }
+        }
+
+        if input.boards.is_empty() {
+            inspection.issue("There are no boards");
+        }
+        inspection
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        let (winning_board, last_drawn) = input.clone().run_until_first_completion()?;
+        Ok(Bingo::calculate_score(winning_board, last_drawn).into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        let (winning_board, last_drawn) = input.clone().run_until_completion()?;
+        Ok(Bingo::calculate_score(winning_board, last_drawn).into())
+    }
+}
 
 #[derive(Debug, Eq, PartialEq, Clone)]
-struct Bingo {
+pub struct Bingo {
     draw_order: Vec<i32>,
     current_draw_index: usize,
     boards: Vec<Board>,
@@ -21,10 +67,12 @@ enum BoardNumber {
 
 impl BoardNumber {
     fn is_drawn(&self) -> bool {
-        if let Drawn(_) = self {
-            true
-        } else {
-            false
+        matches!(self, Drawn(_))
+    }
+
+    fn value(&self) -> i32 {
+        match self {
+            Drawn(n) | NotDrawn(n) => *n,
         }
     }
 
@@ -37,52 +85,39 @@ enum BoardState {
     AlreadyFinished,
 }
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 struct Board {
-    numbers: [[BoardNumber; 5]; 5],
+    numbers: Grid<BoardNumber>,
     state: BoardState,
 }
 
 impl Board {
+    #[cfg(test)]
     fn new(numbers: [[i32; 5]; 5]) -> Self {
-        let mut board_numbers = [[NotDrawn(0); 5]; 5];
-        for i in 0..5 {
-            for j in 0..5 {
-                board_numbers[i][j] = NotDrawn(numbers[i][j]);
-            }
-        }
-
-        Self{numbers: board_numbers, state: BoardState::Running}
+        Self::from_vec(numbers.iter().map(|row| row.to_vec()).collect())
     }
 
     fn from_vec(numbers: Vec<Vec<i32>>) -> Self {
-        let mut board_numbers = [[NotDrawn(0); 5]; 5];
-        for i in 0..5 {
-            for j in 0..5 {
-                board_numbers[i][j] = NotDrawn(numbers[i][j]);
-            }
-        }
+        let numbers = numbers.into_iter()
+            .map(|row| row.into_iter().map(NotDrawn).collect())
+            .collect();
 
-        Self{numbers: board_numbers, state: BoardState::Running}
+        Self{numbers: Grid::from_rows(numbers).expect("Board rows have the same length"), state: BoardState::Running}
     }
 
+    #[cfg(test)]
     fn finished_board(numbers: [[BoardNumber; 5]; 5]) -> Self {
+        let numbers = Grid::from_rows(numbers.iter().map(|row| row.to_vec()).collect()).unwrap();
         Self{numbers, state: BoardState::Bingo}
     }
 
     fn draw(&mut self, drawn_number: i32) -> BoardState {
-        let mut position = None;
-
-        'search: for i in 0..5 {
-            for j in 0..5 {
-                if let NotDrawn(n) = self.numbers[i][j] {
-                    if n == drawn_number {
-                        self.numbers[i][j] = Drawn(n);
-                        position = Some((i, j));
-                        break 'search;
-                    }
-                }
-            }
+        let position = self.numbers.positions()
+            .find(|(_, &n)| n == NotDrawn(drawn_number))
+            .map(|(position, _)| position);
+
+        if let Some(position) = position {
+            self.numbers[position] = Drawn(drawn_number);
         }
 
         if self.state != BoardState::Running {
@@ -90,30 +125,14 @@ impl Board {
             return self.state;
         }
 
-        let (row, column) = if let Some((r, c)) = position {
-            (r, c)
+        let (column, row) = if let Some((c, r)) = position {
+            (c, r)
         } else {
             return BoardState::Running;
         };
 
-        let mut row_is_completed = true;
-        let mut column_is_completed = true;
-
-
-        // (0..5).map(|j| self.numbers[row][j]).all(|n| n == BoardNumber::Drawn())
-        for j in 0..5 {
-            if let NotDrawn(_) = self.numbers[row][j] {
-                row_is_completed = false;
-                break;
-            }
-        }
-
-        for i in 0..5 {
-            if let NotDrawn(_) = self.numbers[i][column] {
-                column_is_completed = false;
-                break;
-            }
-        }
+        let row_is_completed = self.numbers.row(row).all(BoardNumber::is_drawn);
+        let column_is_completed = self.numbers.column(column).all(BoardNumber::is_drawn);
 
         if row_is_completed || column_is_completed {
             self.state = BoardState::Bingo;
@@ -125,18 +144,8 @@ impl Board {
 
 impl Display for Board {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[")?;
-        for l in &self.numbers {
-            write!(f, "[")?;
-            for n in l {
-                write!(f, "{} ", n)?;
-            }
-            write!(f, "]")?;
-        }
-
-        Ok(())
+        write!(f, "{:7}", self.numbers)
     }
-
 }
 
 impl Display for BoardNumber {
@@ -150,58 +159,61 @@ impl Display for BoardNumber {
     }
 }
 
-pub fn part1(input_path: &str) -> Result<i32> {
-    let input = parse_input(input_path)?;
-    let (winning_board, last_drawn) = input.run_until_first_completion();
-    Ok(Bingo::calculate_score(winning_board, last_drawn))
-}
+/// The numbers that appear more than once, in the order of their second appearance.
+fn repeated_numbers<I: IntoIterator<Item = i32>>(numbers: I) -> Vec<i32> {
+    let mut seen = HashSet::new();
+    let mut repeated = vec![];
 
-pub fn part2(input_path: &str) -> Result<i32> {
-    let input = parse_input(input_path)?;
-    let (winning_board, last_drawn) = input.run_until_completion();
-    Ok(Bingo::calculate_score(winning_board, last_drawn))
+    for n in numbers {
+        if !seen.insert(n) && !repeated.contains(&n) {
+            repeated.push(n);
+        }
+    }
+    repeated
 }
 
-fn parse_input(input_path: &str) -> anyhow::Result<Bingo> {
-    let file = File::open(input_path).context("Couldn't open file")?;
-    let mut reader = BufReader::new(file);
-
-    let mut first_line = String::new();
-    reader.read_line(&mut first_line)?;
-
-    let draw_order: Result<Vec<i32>> = first_line.trim().split(",")
-        .map(|s| s.parse()
-            .map_err(anyhow::Error::from)).collect();
+fn join_numbers(numbers: &[i32]) -> String {
+    numbers.iter().map(i32::to_string).collect::<Vec<_>>().join(", ")
+}
 
-    let draw_order = draw_order?;
-    let mut boards = vec![];
+fn parse_input<R: BufRead>(reader: R) -> Result<Bingo> {
+    let (draw_order, boards) = parse_sections(reader, parse_draw_order, parse_board)?;
+    Ok(Bingo::new(draw_order, boards))
+}
 
-    for mut board_input_lines in &reader.lines().into_iter().chunks(6) {
-        board_input_lines.next();
+fn parse_draw_order(header: &Section) -> Result<Vec<i32>> {
+    let (line_number, line) = match header.numbered_lines().collect::<Vec<_>>().as_slice() {
+        &[line] => line,
+        _ => return Err(PuzzleError::invalid_data("Draw order should be a single line")),
+    };
 
-        let mut board = vec![];
-        for input_line in board_input_lines {
-            let input_line = input_line.map_err(anyhow::Error::from)?;
-            let line: Result<Vec<_>> = input_line.split_ascii_whitespace()
-                .map(|s| s.parse::<i32>().map_err(anyhow::Error::from)).collect();
-            let line = line?;
+    line.trim().split(",")
+        .map(|s| s.parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| PuzzleError::parse(Some(line_number), line.trim(), e))
+}
 
-            if line.len() != 5 {
-                return Err(anyhow::Error::msg("Line doesn't have 5 columns"));
-            }
+fn parse_board(section: &Section) -> Result<Board> {
+    let mut board = vec![];
 
-            board.push(line);
-        }
+    for (line_number, input_line) in section.numbered_lines() {
+        let line = input_line.split_ascii_whitespace()
+            .map(|s| s.parse::<i32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| PuzzleError::parse(Some(line_number), input_line, e))?;
 
-        if board.len() != 5 {
-            return Err(anyhow::Error::msg("Board doesn't have 5 lines"));
+        if line.len() != 5 {
+            return Err(PuzzleError::parse(Some(line_number), input_line, ParseError::new("Line doesn't have 5 columns")));
         }
 
-        boards.push(Board::from_vec(board));
+        board.push(line);
+    }
 
+    if board.len() != 5 {
+        return Err(PuzzleError::invalid_data(format!("Board starting on line {} doesn't have 5 lines", section.first_line)));
     }
 
-    Ok(Bingo::new(draw_order, boards))
+    Ok(Board::from_vec(board))
 }
 
 
@@ -212,20 +224,20 @@ impl Bingo {
         Bingo{draw_order, current_draw_index, boards}
     }
 
-    fn run_until_first_completion(mut self) -> (Board, i32) {
+    fn run_until_first_completion(mut self) -> Result<(Board, i32)> {
         for drawn in self.draw_order {
             for board in self.boards.iter_mut() {
                 let current_state = board.draw(drawn);
                 if let BoardState::Bingo = current_state {
-                    return (*board, drawn);
+                    return Ok((board.clone(), drawn));
                 }
             }
         }
 
-        panic!("Didn't have a winner");
+        Err(PuzzleError::no_solution("Didn't have a winner"))
     }
 
-    fn run_until_completion(mut self) -> (Board, i32) {
+    fn run_until_completion(mut self) -> Result<(Board, i32)> {
         let mut completed_boards = 0;
         let total_boards = self.boards.len();
 
@@ -235,24 +247,22 @@ impl Bingo {
                 if let BoardState::Bingo = current_state {
                     completed_boards += 1;
                     if total_boards == completed_boards {
-                        return (*board, drawn);
+                        return Ok((board.clone(), drawn));
                     }
                 }
             }
         }
 
-        panic!("Didn't have a winner");
+        Err(PuzzleError::no_solution("Didn't have a winner"))
     }
 
     fn calculate_score(board: Board, last_drawn_number: i32) -> i32 {
-        let mut board_score = 0;
-        for i in 0..board.numbers.len() {
-            for j in 0..board.numbers[i].len() {
-                if let NotDrawn(n) = board.numbers[i][j] {
-                    board_score += n;
-                }
-            }
-        }
+        let board_score: i32 = board.numbers.iter()
+            .filter_map(|n| match n {
+                NotDrawn(n) => Some(n),
+                Drawn(_) => None,
+            })
+            .sum();
 
         board_score * last_drawn_number
     }
@@ -261,18 +271,71 @@ impl Bingo {
 
 #[cfg(test)]
 mod tests {
-    use crate::input_handling::example_input;
+    use crate::error::ErrorKind;
     use super::*;
 
+    const EXAMPLE: &str = "\
+7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7";
 
     #[test]
     fn test_parse_input() {
-        let input_data = parse_input(&example_input(4)).expect("Couldn't parse input");
+        let input_data = parse_input(EXAMPLE.as_bytes()).expect("Couldn't parse input");
         let expected = create_example_input();
 
         assert_eq!(input_data, expected);
     }
 
+    #[test]
+    fn test_parse_error_has_line_number() {
+        let error = parse_input("1,2\n\n1 2 3 4 5\n1 2 3 4\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "Line 4: Line doesn't have 5 columns in \"1 2 3 4\"");
+    }
+
+    #[test]
+    fn test_parse_tolerates_extra_blank_lines() {
+        let input = "7,4\n\n\n1 2 3 4 5\n1 2 3 4 5\n1 2 3 4 5\n1 2 3 4 5\n1 2 3 4 5\n\n\n";
+        let bingo = parse_input(input.as_bytes()).unwrap();
+
+        assert_eq!(bingo.draw_order, vec![7, 4]);
+        assert_eq!(bingo.boards.len(), 1);
+    }
+
+    #[test]
+    fn test_short_board_is_invalid() {
+        let error = parse_input("7,4\n\n1 2 3 4 5\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "Board starting on line 3 doesn't have 5 lines");
+    }
+
+    #[test]
+    fn test_display_board() {
+        let board = Board::finished_board([[Drawn(14), NotDrawn(21), NotDrawn(17), NotDrawn(24), NotDrawn(4)]; 5]);
+        assert!(board.to_string().starts_with("  D(14)  N(21)  N(17)  N(24)   N(4)\n"));
+    }
+
+    #[test]
+    fn test_no_winner() {
+        let bingo = Bingo::new(vec![1], create_example_input().boards);
+        assert_eq!(bingo.run_until_first_completion().unwrap_err().kind(), ErrorKind::NoSolution);
+    }
+
     #[test]
     fn test_run_first_completion() {
         let input = create_example_input();
@@ -289,7 +352,7 @@ mod tests {
 
         let last_drawn_number = 24;
 
-        assert_eq!(input.run_until_first_completion(), (completed_board, last_drawn_number));
+        assert_eq!(input.run_until_first_completion().unwrap(), (completed_board, last_drawn_number));
     }
 
     #[test]
@@ -310,7 +373,7 @@ mod tests {
 
         let last_drawn_number = 13;
 
-        assert_eq!(input.run_until_completion(), (completed_board, last_drawn_number));
+        assert_eq!(input.run_until_completion().unwrap(), (completed_board, last_drawn_number));
     }
 
     #[test]
@@ -349,7 +412,18 @@ mod tests {
         [22, 11, 13,  6,  5],
         [2,  0, 12,  3,  7]]);
 
-        Bingo::new(draw_order, (vec![board1, board2, board3]))
+        Bingo::new(draw_order, vec![board1, board2, board3])
     }
 
+    #[test]
+    fn test_inspect_duplicates() {
+        let mut input = create_example_input();
+        let inspection = GiantSquid::inspect(&input, &LineNumbers::default());
+        assert_eq!(inspection.figures, vec![("draw length", "27".to_string()), ("boards", "3".to_string())]);
+        assert!(inspection.issues.is_empty());
+
+        input.draw_order.extend([4, 9, 4]);
+        input.boards[1].numbers[(0, 0)] = NotDrawn(15);
+        assert_eq!(GiantSquid::inspect(&input, &LineNumbers::default()).issues, vec!["Draw order repeats 4, 9", "Board 2 repeats 15"]);
+    }
 }
\ No newline at end of file
@@ -1,100 +1,150 @@
 use core::iter;
+use std::fmt::{Display, Formatter};
 use std::ops::RangeInclusive;
 use std::str::FromStr;
-use anyhow::Result;
-use itertools::Itertools;
-use crate::input_handling::parse_file_line_by_line;
-
-pub fn part1(path: &str) -> Result<i32> {
-    let input = parse_input(path)?;
-    let plot = plot_line_overlap(&input, false);
-    Ok(count_overlaping_lines(&plot))
-}
+use std::io::BufRead;
+use crate::error::{ParseError, PuzzleError, Result};
+use crate::input_handling::{parse_line_by_line, validate_line_by_line, Grid, LineNumbers, Pattern};
+use crate::solution::{Answer, Inspection, Solution};
+
+pub struct HydrothermalVenture;
+
+impl Solution for HydrothermalVenture {
+    const DAY: u32 = 5;
+    const TITLE: &'static str = "Hydrothermal Venture";
+
+    type Input = Vec<VentLine>;
+
+    fn parse<R: BufRead>(reader: R) -> Result<Self::Input> {
+        parse_input(reader)
+    }
+
+    fn parse_leniently<R: BufRead>(reader: R) -> Result<(Self::Input, Vec<PuzzleError>)> {
+        validate_line_by_line(reader)
+    }
+
+    fn inspect(input: &Self::Input, lines: &LineNumbers) -> Inspection {
+        let mut inspection = Inspection::default();
+        inspection.figure("vent lines", input.len());
+
+        match input.iter().map(|l| l.start.x.max(l.start.y).max(l.end.x).max(l.end.y)).max() {
+            Some(max) => {
+                inspection.figure("max coordinate", max);
+                if let Err(e) = plot_size(input) {
+                    inspection.issue(e.to_string());
+                }
+            }
+            None => inspection.issue("Input is empty"),
+        }
+
+        for (i, vent) in input.iter().enumerate() {
+            let dx = vent.start.x.abs_diff(vent.end.x);
+            let dy = vent.start.y.abs_diff(vent.end.y);
+
+            if dx != 0 && dy != 0 && dx != dy {
+                inspection.issue(format!("Line {} ({}) isn't horizontal, vertical or at 45 degrees", lines.of(i), vent));
+            }
+        }
+        inspection
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        let plot = plot_line_overlap(input, false)?;
+        Ok(count_overlaping_lines(&plot).into())
+    }
 
-pub fn part2(path: &str) -> Result<i32> {
-    let input = parse_input(path)?;
-    let plot = plot_line_overlap(&input, true);
-    Ok(count_overlaping_lines(&plot))
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        let plot = plot_line_overlap(input, true)?;
+        Ok(count_overlaping_lines(&plot).into())
+    }
 }
 
 
 #[derive(Debug, Eq, PartialEq, Clone)]
-struct VentLine {
+pub struct VentLine {
     start: VentPoint,
     end: VentPoint,
 }
 
 impl VentLine {
+    #[cfg(test)]
     fn new(start: VentPoint, end: VentPoint) -> Self {
         Self{start, end}
     }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
-struct VentPoint {
+pub struct VentPoint {
     x: usize,
     y: usize,
 }
 
 impl VentPoint {
+    #[cfg(test)]
     fn new(x: usize, y: usize) -> Self {
         Self{x, y}
     }
 }
 
-impl FromStr for VentPoint {
-    type Err = anyhow::Error;
+impl Display for VentLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{} -> {},{}", self.start.x, self.start.y, self.end.x, self.end.y)
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<_> = s.split(",").collect();
-        if parts.len() != 2 {
-            return Err(anyhow::Error::msg("Wrong number of coordinates"));
-        }
+const VENT_POINT: Pattern = Pattern::new("{x},{y}");
+const VENT_LINE: Pattern = Pattern::new("{x1},{y1} -> {x2},{y2}");
 
-        let x = parts[0].parse()?;
-        let y = parts[1].parse()?;
+impl FromStr for VentPoint {
+    type Err = ParseError;
 
-        Ok(VentPoint{x, y})
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = VENT_POINT.captures(s)?;
+        Ok(VentPoint{x: fields.get("x")?, y: fields.get("y")?})
     }
 }
 
 impl FromStr for VentLine {
-    type Err = anyhow::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<_> = s.split("->").collect();
-        if parts.len() != 2 {
-            return Err(anyhow::Error::msg("Wrong number of parts"));
-        }
-
-        let start = parts[0].trim().parse()?;
-        let end = parts[1].trim().parse()?;
+        let fields = VENT_LINE.captures(s)?;
+        let start = VentPoint{x: fields.get("x1")?, y: fields.get("y1")?};
+        let end = VentPoint{x: fields.get("x2")?, y: fields.get("y2")?};
 
         Ok(VentLine{start, end})
-
     }
 }
 
 
-fn parse_input(path: &str) -> anyhow::Result<Vec<VentLine>> {
-    parse_file_line_by_line(path)
+fn parse_input<R: BufRead>(reader: R) -> Result<Vec<VentLine>> {
+    parse_line_by_line(reader)
 }
 
-fn plot_line_overlap(input: &[VentLine], count_diagonals: bool) -> Vec<Vec<i32>> {
-    let width = input.iter()
-        .map(|l| l.start.x.max(l.end.x))
-        .max()
-        .expect("Input is empty") as usize + 1;
-    let height = input.iter()
-        .map(|l| l.start.y.max(l.end.y))
-        .max()
-        .expect("Input is empty") as usize + 1;
+/// The most points the plot may have, so that far-off coordinates fail instead of exhausting memory.
+const MAX_PLOT_POINTS: usize = 100_000_000;
+
+/// The width and height of a plot covering every vent line.
+fn plot_size(input: &[VentLine]) -> Result<(usize, usize)> {
+    let max_x = input.iter().map(|l| l.start.x.max(l.end.x)).max();
+    let max_y = input.iter().map(|l| l.start.y.max(l.end.y)).max();
+    let (max_x, max_y) = max_x.zip(max_y).ok_or_else(|| PuzzleError::invalid_data("Input is empty"))?;
+
+    let width = max_x.saturating_add(1);
+    let height = max_y.saturating_add(1);
+    match width.checked_mul(height) {
+        Some(points) if points <= MAX_PLOT_POINTS => Ok((width, height)),
+        _ => Err(PuzzleError::invalid_data(format!("A {}x{} plot is larger than the limit of {} points", width, height, MAX_PLOT_POINTS))),
+    }
+}
 
-    let mut plane = vec![vec![0; width]; height];
+fn plot_line_overlap(input: &[VentLine], count_diagonals: bool) -> Result<Grid<i32>> {
+    let (width, height) = plot_size(input)?;
+    let mut plane = Grid::new(width, height, 0);
 
     for line in input {
-        let mut x_changes = line.start.x != line.end.x;
-        let mut y_changes = line.start.y != line.end.y;
+        let x_changes = line.start.x != line.end.x;
+        let y_changes = line.start.y != line.end.y;
 
         if (x_changes && y_changes) && !count_diagonals {
             continue;
@@ -102,15 +152,16 @@ fn plot_line_overlap(input: &[VentLine], count_diagonals: bool) -> Vec<Vec<i32>>
 
         let x_iter = iter_changes(line.start.x, line.end.x);
         let y_iter = iter_changes(line.start.y, line.end.y);
+        let length = line.start.x.abs_diff(line.end.x).max(line.start.y.abs_diff(line.end.y)) + 1;
 
-        let results: Vec<_> = x_iter.zip(y_iter).collect();
+        let results: Vec<_> = x_iter.zip(y_iter).take(length).collect();
 
         for (x, y) in results {
-            plane[y][x] += 1;
+            plane[(x, y)] += 1;
         }
     }
 
-    plane
+    Ok(plane)
 }
 
 enum ChangesIter {
@@ -152,9 +203,8 @@ fn iter_changes(start: usize, end: usize) -> Changes {
 }
 
 
-fn count_overlaping_lines(plot: &Vec<Vec<i32>>) -> i32 {
+fn count_overlaping_lines(plot: &Grid<i32>) -> i32 {
     plot.iter()
-        .flatten()
         .filter(|&e| *e >= 2)
         .count() as i32
 }
@@ -162,18 +212,72 @@ fn count_overlaping_lines(plot: &Vec<Vec<i32>>) -> i32 {
 
 #[cfg(test)]
 mod tests {
-    use crate::input_handling::example_input;
     use super::*;
 
+    const EXAMPLE: &str = "\
+0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
+
     #[test]
     fn test_parse_input() {
-        let input = parse_input(&example_input(5)).unwrap();
+        let input = parse_input(EXAMPLE.as_bytes()).unwrap();
         assert_eq!(input.as_slice(), &create_example_input());
     }
 
+    #[test]
+    fn test_parse_error_chain() {
+        let error = parse_input("0,9 -> 5,9\n8,x -> 0,8\n".as_bytes()).unwrap_err();
+        assert_eq!(error.chain(), "Line 2, column 3: Invalid y1 \"x\" in \"8,x -> 0,8\": invalid digit found in string");
+        assert_eq!("3,4 -> 5".parse::<VentLine>().unwrap_err().to_string(), "Expected \",\" after x2");
+        assert_eq!("3,4".parse::<VentPoint>().unwrap(), VentPoint::new(3, 4));
+    }
+
+    #[test]
+    fn test_inspect() {
+        let mut input = create_example_input();
+        let inspection = HydrothermalVenture::inspect(&input, &LineNumbers::default());
+        assert_eq!(inspection.figures, vec![("vent lines", "10".to_string()), ("max coordinate", "9".to_string())]);
+        assert!(inspection.issues.is_empty());
+
+        input.push(VentLine::new(VentPoint::new(1, 1), VentPoint::new(2, 3)));
+        assert_eq!(HydrothermalVenture::inspect(&input, &LineNumbers::default()).issues, vec![
+            "Line 11 (1,1 -> 2,3) isn't horizontal, vertical or at 45 degrees",
+        ]);
+    }
+
+    #[test]
+    fn test_single_point_lines() {
+        let input = parse_input("0,0 -> 0,0\n0,0 -> 0,2\n".as_bytes()).unwrap();
+        let expected_plot = Grid::from_rows(vec![vec![2], vec![1], vec![1]]).unwrap();
+
+        assert_eq!(plot_line_overlap(&input, true).unwrap(), expected_plot);
+        assert_eq!(HydrothermalVenture::part1(&input).unwrap(), Answer::from(1));
+        assert!(HydrothermalVenture::inspect(&input, &LineNumbers::default()).issues.is_empty());
+    }
+
+    #[test]
+    fn test_far_off_coordinates_are_invalid() {
+        let input = parse_input("1,1 -> 1000000,1000000\n".as_bytes()).unwrap();
+        let message = "A 1000001x1000001 plot is larger than the limit of 100000000 points";
+
+        assert_eq!(HydrothermalVenture::part2(&input).unwrap_err().to_string(), message);
+        assert_eq!(HydrothermalVenture::inspect(&input, &LineNumbers::default()).issues, vec![message]);
+
+        let input = parse_input(format!("0,0 -> {},0\n", usize::MAX).as_bytes()).unwrap();
+        assert!(HydrothermalVenture::part1(&input).is_err());
+    }
+
     #[test]
     fn test_plot_line_overlap_without_diagonals() {
-        let expected_plot = vec![
+        let expected_plot = Grid::from_rows(vec![
             vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0],
             vec![0, 0, 1, 0, 0, 0, 0, 1, 0, 0],
             vec![0, 0, 1, 0, 0, 0, 0, 1, 0, 0],
@@ -184,9 +288,9 @@ mod tests {
             vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
             vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
             vec![2, 2, 2, 1, 1, 1, 0, 0, 0, 0],
-        ];
+        ]).unwrap();
 
-        assert_eq!(plot_line_overlap(&create_example_input(), false),expected_plot);
+        assert_eq!(plot_line_overlap(&create_example_input(), false).unwrap(), expected_plot);
     }
 
     #[test]
@@ -209,7 +313,7 @@ mod tests {
 
     #[test]
     fn test_plot_line_overlap_with_diagonals() {
-        let expected_plot = vec![
+        let expected_plot = Grid::from_rows(vec![
             vec![1, 0, 1, 0, 0, 0, 0, 1, 1, 0],
             vec![0, 1, 1, 1, 0, 0, 0, 2, 0, 0],
             vec![0, 0, 2, 0, 1, 0, 1, 1, 1, 0],
@@ -220,14 +324,14 @@ mod tests {
             vec![0, 1, 0, 0, 0, 0, 0, 1, 0, 0],
             vec![1, 0, 0, 0, 0, 0, 0, 0, 1, 0],
             vec![2, 2, 2, 1, 1, 1, 0, 0, 0, 0],
-        ];
+        ]).unwrap();
 
-        assert_eq!(plot_line_overlap(&create_example_input(), true),expected_plot);
+        assert_eq!(plot_line_overlap(&create_example_input(), true).unwrap(), expected_plot);
     }
 
     #[test]
     fn test_count_overlaping_lines() {
-        let expected_plot = vec![
+        let expected_plot = Grid::from_rows(vec![
             vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0],
             vec![0, 0, 1, 0, 0, 0, 0, 1, 0, 0],
             vec![0, 0, 1, 0, 0, 0, 0, 1, 0, 0],
@@ -238,7 +342,7 @@ mod tests {
             vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
             vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
             vec![2, 2, 2, 1, 1, 1, 0, 0, 0, 0],
-        ];
+        ]).unwrap();
 
         assert_eq!(count_overlaping_lines(&expected_plot), 5);
     }
@@ -0,0 +1,229 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+pub type Result<T, E = PuzzleError> = std::result::Result<T, E>;
+
+type BoxedError = Box<dyn Error + Send + Sync>;
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ErrorKind {
+    MissingInput,
+    Io,
+    Parse,
+    InvalidData,
+    NoSolution,
+    Panic,
+}
+
+impl ErrorKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::MissingInput => "missing_input",
+            ErrorKind::Io => "io",
+            ErrorKind::Parse => "parse",
+            ErrorKind::InvalidData => "invalid_data",
+            ErrorKind::NoSolution => "no_solution",
+            ErrorKind::Panic => "panic",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ErrorKind::MissingInput => "missing input",
+            ErrorKind::Io => "I/O error",
+            ErrorKind::Parse => "parse error",
+            ErrorKind::InvalidData => "invalid puzzle data",
+            ErrorKind::NoSolution => "no solution",
+            ErrorKind::Panic => "panicked",
+        }
+    }
+}
+
+/// Everything that can go wrong between locating a puzzle input and producing an answer.
+#[derive(Debug)]
+pub enum PuzzleError {
+    MissingInput{path: String, source: io::Error},
+    Io(io::Error),
+    Parse{path: Option<String>, line: Option<usize>, column: Option<usize>, text: String, source: BoxedError},
+    InvalidData(String),
+    NoSolution(String),
+    Panicked(String),
+}
+
+impl PuzzleError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            PuzzleError::MissingInput{..} => ErrorKind::MissingInput,
+            PuzzleError::Io(_) => ErrorKind::Io,
+            PuzzleError::Parse{..} => ErrorKind::Parse,
+            PuzzleError::InvalidData(_) => ErrorKind::InvalidData,
+            PuzzleError::NoSolution(_) => ErrorKind::NoSolution,
+            PuzzleError::Panicked(_) => ErrorKind::Panic,
+        }
+    }
+
+    /// The input line a parse error is on.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            PuzzleError::Parse{line, ..} => *line,
+            _ => None,
+        }
+    }
+
+    /// Wraps an error from parsing `text`. If it's a [`ParseError`] that knows its column, the
+    /// column is kept.
+    pub fn parse<E: Into<BoxedError>>(line: Option<usize>, text: &str, error: E) -> Self {
+        let source = error.into();
+        let column = source.downcast_ref::<ParseError>().and_then(|e| e.column);
+
+        PuzzleError::Parse{path: None, line, column, text: text.to_string(), source}
+    }
+
+    /// Records which file a parse error came from. Other errors are returned unchanged.
+    pub fn in_file<S: Into<String>>(self, file: S) -> Self {
+        match self {
+            PuzzleError::Parse{path: None, line, column, text, source} =>
+                PuzzleError::Parse{path: Some(file.into()), line, column, text, source},
+            e => e,
+        }
+    }
+
+    pub fn invalid_data<S: Into<String>>(message: S) -> Self {
+        PuzzleError::InvalidData(message.into())
+    }
+
+    pub fn no_solution<S: Into<String>>(message: S) -> Self {
+        PuzzleError::NoSolution(message.into())
+    }
+
+    /// This error followed by each of its sources, separated by `: `.
+    pub fn chain(&self) -> String {
+        let mut message = self.to_string();
+        let mut source = self.source();
+
+        while let Some(e) = source {
+            message.push_str(": ");
+            message.push_str(&e.to_string());
+            source = e.source();
+        }
+
+        message
+    }
+}
+
+impl Display for PuzzleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PuzzleError::MissingInput{path, ..} => write!(f, "Couldn't open {}", path),
+            PuzzleError::Io(_) => write!(f, "Couldn't read input"),
+            PuzzleError::Parse{path, line, column, text, source} => {
+                match (path, line, column) {
+                    (Some(p), Some(l), Some(c)) => write!(f, "{}:{}:{}: ", p, l, c)?,
+                    (Some(p), Some(l), None) => write!(f, "{}:{}: ", p, l)?,
+                    (Some(p), None, _) => write!(f, "{}: ", p)?,
+                    (None, Some(l), Some(c)) => write!(f, "Line {}, column {}: ", l, c)?,
+                    (None, Some(l), None) => write!(f, "Line {}: ", l)?,
+                    (None, None, Some(c)) => write!(f, "Column {}: ", c)?,
+                    (None, None, None) => (),
+                }
+                write!(f, "{} in {:?}", source, text)
+            }
+            PuzzleError::InvalidData(message) => write!(f, "{}", message),
+            PuzzleError::NoSolution(message) => write!(f, "{}", message),
+            PuzzleError::Panicked(message) => write!(f, "Panicked: {}", message),
+        }
+    }
+}
+
+impl Error for PuzzleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PuzzleError::MissingInput{source, ..} => Some(source),
+            PuzzleError::Io(source) => Some(source),
+            // The parse error's own message is already part of ours.
+            PuzzleError::Parse{source, ..} => source.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PuzzleError {
+    fn from(e: io::Error) -> Self {
+        PuzzleError::Io(e)
+    }
+}
+
+/// The error of a single value's `FromStr`, before it's known which line it came from.
+#[derive(Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub column: Option<usize>,
+    source: Option<BoxedError>,
+}
+
+impl ParseError {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        ParseError{message: message.into(), column: None, source: None}
+    }
+
+    pub fn at<S: Into<String>>(column: usize, message: S) -> Self {
+        ParseError{column: Some(column), ..ParseError::new(message)}
+    }
+
+    pub fn caused_by<E: Into<BoxedError>>(mut self, source: E) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_ref().map(|e| e.as_ref() as &(dyn Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_keeps_column_and_chain() {
+        let cause = "x".parse::<i32>().unwrap_err();
+        let error = PuzzleError::parse(Some(3), "up x", ParseError::at(4, "Amount is not a number").caused_by(cause));
+
+        assert_eq!(error.kind(), ErrorKind::Parse);
+        assert_eq!(error.to_string(), "Line 3, column 4: Amount is not a number in \"up x\"");
+        assert_eq!(error.chain(), "Line 3, column 4: Amount is not a number in \"up x\": invalid digit found in string");
+    }
+
+    #[test]
+    fn test_parse_error_in_file() {
+        let error = PuzzleError::parse(Some(212), "3,4 -> 5", ParseError::new("Wrong number of coordinates"))
+            .in_file("inputs/5.txt");
+
+        assert_eq!(error.chain(), "inputs/5.txt:212: Wrong number of coordinates in \"3,4 -> 5\"");
+        assert_eq!(PuzzleError::no_solution("None").in_file("inputs/5.txt").chain(), "None");
+    }
+
+    #[test]
+    fn test_missing_input_has_io_source() {
+        let error = PuzzleError::MissingInput{path: "inputs/6.txt".to_string(), source: io::Error::from(io::ErrorKind::NotFound)};
+
+        assert_eq!(error.kind(), ErrorKind::MissingInput);
+        assert!(error.source().is_some());
+        assert!(error.chain().starts_with("Couldn't open inputs/6.txt: "));
+    }
+
+    #[test]
+    fn test_messages_without_source() {
+        assert_eq!(PuzzleError::no_solution("Didn't have a winner").chain(), "Didn't have a winner");
+        assert_eq!(PuzzleError::invalid_data("Empty input").kind().description(), "invalid puzzle data");
+    }
+}
@@ -1,27 +1,1064 @@
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::io::{prelude::BufRead, BufReader};
+use std::io::{self, prelude::BufRead, BufReader, Read};
+use std::iter;
+use std::ops::{Index, IndexMut};
 use std::str::FromStr;
-use anyhow::{self, Context, Error};
+use flate2::bufread::MultiGzDecoder;
+use itertools::Either;
+use crate::error::{ParseError, PuzzleError, Result};
 
-pub const DEFAULT_INPUT_FOLDER: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/", "inputs");
+pub const DEFAULT_INPUT_FOLDER: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/", "inputs");
+pub const DEFAULT_INPUT_PATTERN: &str = "{day}.txt";
+pub const EXAMPLE_INPUT_PATTERN: &str = "{day}_example.txt";
 
-#[cfg(test)]
-pub fn example_input(day: u32) -> String {
-    format!("{}/{}_example.txt", DEFAULT_INPUT_FOLDER, day)
+pub const INPUT_FOLDER_ENV: &str = "AOC_INPUT_DIR";
+pub const INPUT_PATTERN_ENV: &str = "AOC_INPUT_PATTERN";
+
+/// Works out where each day's input lives. The folder and file name pattern come from the command
+/// line, then the environment, then the compile-time defaults. `{day}` and `{day:02}` in the
+/// pattern are replaced by the day number. Examples follow the same pattern with `_example` before
+/// the extension, so `{day:02}.in` has examples named `{day:02}_example.in`.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct InputLocator {
+    pub folder: String,
+    pub pattern: String,
+    overrides: HashMap<u32, String>,
+}
+
+impl InputLocator {
+    pub fn new(folder: Option<String>, pattern: Option<String>) -> Self {
+        let folder = folder
+            .or_else(|| env::var(INPUT_FOLDER_ENV).ok())
+            .unwrap_or_else(|| DEFAULT_INPUT_FOLDER.to_string());
+        let pattern = pattern
+            .or_else(|| env::var(INPUT_PATTERN_ENV).ok())
+            .unwrap_or_else(|| DEFAULT_INPUT_PATTERN.to_string());
+
+        InputLocator{folder, pattern, overrides: HashMap::new()}
+    }
+
+    pub fn with_override(mut self, day: u32, path: String) -> Self {
+        self.overrides.insert(day, path);
+        self
+    }
+
+    pub fn path_for(&self, day: u32) -> String {
+        if let Some(path) = self.overrides.get(&day) {
+            return path.clone();
+        }
+
+        format!("{}/{}", self.folder, file_name(&self.pattern, day))
+    }
+
+    pub fn example_path_for(&self, day: u32) -> String {
+        format!("{}/{}", self.folder, file_name(&example_pattern(&self.pattern), day))
+    }
+
+    pub fn is_overridden(&self, day: u32) -> bool {
+        self.overrides.contains_key(&day)
+    }
+
+    pub fn file_in_folder(&self, name: &str) -> String {
+        format!("{}/{}", self.folder, name)
+    }
+}
+
+fn example_pattern(pattern: &str) -> String {
+    let name_start = pattern.rfind('/').map_or(0, |i| i + 1);
+    let stem_start = pattern[name_start..].rfind('}').map_or(name_start, |i| name_start + i + 1);
+    let extension_start = pattern[stem_start..].find('.').map_or(pattern.len(), |i| stem_start + i);
+
+    format!("{}_example{}", &pattern[..extension_start], &pattern[extension_start..])
+}
+
+fn file_name(pattern: &str, day: u32) -> String {
+    pattern
+        .replace("{day:02}", &format!("{:02}", day))
+        .replace("{day}", &day.to_string())
+}
+
+/// Where a puzzle input is read from: a file on disk, or text that was piped in or given inline.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum InputSource {
+    File(String),
+    Text(String),
+}
+
+impl InputSource {
+    pub fn open(&self) -> Result<Box<dyn BufRead + '_>> {
+        match self {
+            InputSource::File(path) => Ok(Box::new(open_file(path)?)),
+            InputSource::Text(text) => Ok(Box::new(Normalised::new(text.as_bytes()))),
+        }
+    }
+}
+
+impl Display for InputSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputSource::File(path) => write!(f, "{}", path),
+            InputSource::Text(_) => write!(f, "<inline input>"),
+        }
+    }
+}
+
+/// Opens an input file, decompressing it on the fly if it's gzip or zstd compressed.
+pub fn open_file(path: &str) -> Result<Normalised<Box<dyn BufRead>>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(PuzzleError::MissingInput{path: path.to_string(), source: e}),
+        Err(e) => return Err(PuzzleError::Io(e)),
+    };
+
+    let mut reader = BufReader::new(file);
+    let contents: Box<dyn BufRead> = match Compression::detect(path, reader.fill_buf()?) {
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        Compression::None => Box::new(reader),
+    };
+
+    Ok(Normalised::new(contents))
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Goes by the file extension, or failing that by the magic bytes the file starts with.
+    fn detect(path: &str, start: &[u8]) -> Self {
+        if path.ends_with(".gz") || start.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if path.ends_with(".zst") || start.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+const NORMALISED_CHUNK: usize = 8 * 1024;
+
+/// Wraps an input so that every day sees text in the same shape: no UTF-8 byte order mark, `\n`
+/// line endings, no whitespace at either end of a line, runs of spaces and tabs collapsed to a
+/// single space, and no blank lines at the end. Blank lines between other lines are kept.
+pub struct Normalised<R> {
+    inner: R,
+    partial_line: Vec<u8>,
+    lines: NormalisedLines,
+    position: usize,
+}
+
+impl<R: BufRead> Normalised<R> {
+    pub fn new(inner: R) -> Self {
+        Normalised{inner, partial_line: vec![], lines: NormalisedLines::default(), position: 0}
+    }
+
+    /// Normalises lines until there's a chunk's worth of output or the input ends. Lines are
+    /// taken straight from the inner reader's buffer, and only copied aside when they cross
+    /// the end of it.
+    fn read_next_lines(&mut self) -> io::Result<()> {
+        self.lines.output.clear();
+        self.position = 0;
+
+        while self.lines.output.len() < NORMALISED_CHUNK {
+            let available = self.inner.fill_buf()?;
+            if available.is_empty() {
+                if !self.partial_line.is_empty() {
+                    self.lines.push(&self.partial_line)?;
+                    self.partial_line.clear();
+                }
+                break;
+            }
+
+            let consumed = match available.iter().rposition(|&b| b == b'\n') {
+                None => {
+                    self.partial_line.extend_from_slice(available);
+                    available.len()
+                }
+                Some(last) => {
+                    let mut lines = available[..last].split(|&b| b == b'\n');
+                    if !self.partial_line.is_empty() {
+                        self.partial_line.extend_from_slice(lines.next().unwrap_or_default());
+                        self.lines.push(&self.partial_line)?;
+                        self.partial_line.clear();
+                    }
+                    for line in lines {
+                        self.lines.push(line)?;
+                    }
+                    last + 1
+                }
+            };
+            self.inner.consume(consumed);
+        }
+
+        Ok(())
+    }
+}
+
+/// The normalised output, with blank lines held back until a non-blank line follows them, so the
+/// ones at the end are never emitted.
+#[derive(Default)]
+struct NormalisedLines {
+    output: Vec<u8>,
+    held_blank_lines: usize,
+    started: bool,
+}
+
+impl NormalisedLines {
+    fn push(&mut self, mut line: &[u8]) -> io::Result<()> {
+        if !self.started {
+            line = line.strip_prefix("\u{feff}".as_bytes()).unwrap_or(line);
+            self.started = true;
+        }
+
+        if is_normalised(line) {
+            self.output.extend(iter::repeat_n(b'\n', self.held_blank_lines));
+            self.held_blank_lines = 0;
+            self.output.extend_from_slice(line);
+            self.output.push(b'\n');
+            return Ok(());
+        }
+
+        let line = std::str::from_utf8(line)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))?;
+        let mut words = line.split_whitespace().peekable();
+        if words.peek().is_none() {
+            self.held_blank_lines += 1;
+            return Ok(());
+        }
+
+        self.output.extend(iter::repeat_n(b'\n', self.held_blank_lines));
+        self.held_blank_lines = 0;
+
+        for (i, word) in words.enumerate() {
+            if i > 0 {
+                self.output.push(b' ');
+            }
+            self.output.extend_from_slice(word.as_bytes());
+        }
+        self.output.push(b'\n');
+        Ok(())
+    }
+}
+
+/// Whether a line is already non-empty printable ASCII with single spaces between words, which
+/// is most lines of most inputs, so it can be copied as it is.
+fn is_normalised(line: &[u8]) -> bool {
+    let mut after_space = true;
+
+    for &b in line {
+        match b {
+            b' ' if after_space => return false,
+            b' ' => after_space = true,
+            b'!'..=b'~' => after_space = false,
+            _ => return false,
+        }
+    }
+
+    !after_space
+}
+
+impl<R: BufRead> Read for Normalised<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Normalised<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.position >= self.lines.output.len() {
+            self.read_next_lines()?;
+        }
+        Ok(&self.lines.output[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.lines.output.len());
+    }
 }
 
-pub fn parse_file_line_by_line<T: FromStr>(path: &str) -> anyhow::Result<Vec<T>>
+// Path-based wrappers over the reader-based parsers, for use outside the runner, which opens its
+// own inputs.
+#[allow(dead_code)]
+pub fn parse_file_line_by_line<T>(path: &str) -> Result<Vec<T>>
+    where T: FromStr,
+          T::Err: Error + Send + Sync + 'static,
 {
-    let file = File::open(path).context("Couldn't open file")?;
-    let reader = BufReader::new(file);
+    open_file(path).and_then(parse_line_by_line).map_err(|e| e.in_file(path))
+}
+
+/// Parses the lines of the file at `path` one at a time as they're read. Failing to open the file
+/// is the first and only item.
+#[allow(dead_code)]
+pub fn lines_parsed<T>(path: &str) -> impl Iterator<Item = Result<T>>
+    where T: FromStr,
+          T::Err: Error + Send + Sync + 'static,
+{
+    let path = path.to_string();
+    let parsed = match open_file(&path) {
+        Ok(reader) => Either::Left(parsed_lines(reader)),
+        Err(e) => Either::Right(iter::once(Err(e))),
+    };
+
+    parsed.map(move |line| line.map_err(|e| e.in_file(&path)))
+}
+
+pub fn parse_line_by_line<T, R: BufRead>(reader: R) -> Result<Vec<T>>
+    where T: FromStr,
+          T::Err: Error + Send + Sync + 'static,
+{
+    InputText::read(reader)?.records().collect()
+}
+
+/// A whole input read into memory at once, handing out lines and records that borrow from it
+/// instead of allocating a `String` per line.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct InputText {
+    text: String,
+}
+
+impl InputText {
+    pub fn read<R: BufRead>(mut reader: R) -> Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Ok(InputText{text})
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.text.lines()
+    }
+
+    /// Each line parsed as a `T`, with parse errors carrying the line number.
+    pub fn records<T>(&self) -> impl Iterator<Item = Result<T>> + '_
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static,
+    {
+        self.lines().enumerate().map(|(i, line)| parse_line(i + 1, line))
+    }
+}
 
+/// Parses the lines of `reader` one at a time as they're read.
+pub fn parsed_lines<T, R: BufRead>(reader: R) -> impl Iterator<Item = Result<T>>
+    where T: FromStr,
+          T::Err: Error + Send + Sync + 'static,
+{
     reader.lines()
-        .map(line_to_parsed)
-        .collect()
+        .enumerate()
+        .map(|(i, line)| parse_line(i + 1, &line?))
+}
+
+/// The format of a line as literal text with named fields in braces, e.g. `"{dir} {n}"` or
+/// `"{x1},{y1} -> {x2},{y2}"`. A field takes everything up to the text that follows it in the
+/// pattern, without surrounding spaces, so neighbouring fields need some text between them. Spaces
+/// around that text are optional in the line, so `"0,9->5,9"` matches too.
+#[derive(Debug, Copy, Clone)]
+pub struct Pattern(&'static str);
+
+impl Pattern {
+    pub const fn new(pattern: &'static str) -> Self {
+        Pattern(pattern)
+    }
+
+    /// Matches the whole of `line` and returns the text of each field, without allocating.
+    pub fn captures<'a>(&self, line: &'a str) -> std::result::Result<Captures<'a>, ParseError> {
+        let mut fields = Captures{fields: [None; MAX_FIELDS]};
+        let mut rest = line;
+        let mut pattern = self.0;
+        let mut open_field = None;
+        let column = |rest: &str| line.len() - rest.len() + 1;
+
+        loop {
+            let (literal, remaining) = pattern.split_at(pattern.find('{').unwrap_or(pattern.len()));
+
+            if !literal.is_empty() {
+                // Spaces around a literal are optional, but a literal of only spaces still has to
+                // separate its fields.
+                let trimmed = literal.trim();
+
+                if let Some(name) = open_field.take() {
+                    let end = if trimmed.is_empty() { find_whitespace(rest) } else { find_literal(rest, trimmed) }
+                        .ok_or_else(|| ParseError::at(column(rest), format!("Expected {:?} after {}", literal, name)))?;
+                    fields.push(Field::new(name, &rest[..end], column(rest))?, self.0);
+                    rest = &rest[end..];
+                }
+
+                rest = rest.trim_start().strip_prefix(trimmed)
+                    .ok_or_else(|| ParseError::at(column(rest), format!("Expected {:?}", literal)))?
+                    .trim_start();
+            }
+
+            if remaining.is_empty() {
+                break;
+            }
+
+            let close = remaining.find('}').unwrap_or_else(|| panic!("Unclosed field in pattern {:?}", self.0));
+            assert!(open_field.is_none(), "Fields need text between them in pattern {:?}", self.0);
+            open_field = Some(&remaining[1..close]);
+            pattern = &remaining[close + 1..];
+        }
+
+        match open_field {
+            Some(name) => fields.push(Field::new(name, rest, column(rest))?, self.0),
+            None if !rest.is_empty() => return Err(ParseError::at(column(rest), format!("Unexpected {:?}", rest))),
+            None => (),
+        }
+
+        Ok(fields)
+    }
+}
+
+/// `text.find(literal)`, but without setting up a substring searcher, which costs more than the
+/// search itself on lines this short.
+fn find_literal(text: &str, literal: &str) -> Option<usize> {
+    let first = literal.chars().next()?;
+    let mut start = 0;
+
+    while let Some(i) = text[start..].find(first) {
+        let at = start + i;
+        if text[at..].starts_with(literal) {
+            return Some(at);
+        }
+        start = at + first.len_utf8();
+    }
+
+    None
+}
+
+/// Where the first run of whitespace after any leading whitespace starts.
+fn find_whitespace(text: &str) -> Option<usize> {
+    let start = text.len() - text.trim_start().len();
+    text[start..].find(char::is_whitespace).map(|i| start + i)
+}
+
+const MAX_FIELDS: usize = 8;
+
+#[derive(Debug, Copy, Clone)]
+struct Field<'a> {
+    name: &'static str,
+    text: &'a str,
+    column: usize,
+}
+
+impl<'a> Field<'a> {
+    fn new(name: &'static str, text: &'a str, column: usize) -> std::result::Result<Self, ParseError> {
+        let trimmed = text.trim_start();
+        let column = column + text.len() - trimmed.len();
+        let text = trimmed.trim_end();
+
+        if text.is_empty() {
+            return Err(ParseError::at(column, format!("Missing {}", name)));
+        }
+
+        Ok(Field{name, text, column})
+    }
+}
+
+/// The fields of a line that matched a [`Pattern`].
+#[derive(Debug)]
+pub struct Captures<'a> {
+    fields: [Option<Field<'a>>; MAX_FIELDS],
 }
 
-fn line_to_parsed<U: FromStr>(line: std::io::Result<String>) -> anyhow::Result<U>
+impl<'a> Captures<'a> {
+    fn push(&mut self, field: Field<'a>, pattern: &str) {
+        let slot = self.fields.iter_mut().find(|f| f.is_none())
+            .unwrap_or_else(|| panic!("Pattern {:?} has more than {} fields", pattern, MAX_FIELDS));
+        *slot = Some(field);
+    }
+
+    /// Parses the field `name`. Asking for a field the pattern doesn't have is a bug, and panics.
+    pub fn get<T>(&self, name: &str) -> std::result::Result<T, ParseError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static,
+    {
+        let field = self.field(name);
+        field.text.parse()
+            .map_err(|e| ParseError::at(field.column, format!("Invalid {} {:?}", name, field.text)).caused_by(e))
+    }
+
+    pub fn text(&self, name: &str) -> &'a str {
+        self.field(name).text
+    }
+
+    pub fn column(&self, name: &str) -> usize {
+        self.field(name).column
+    }
+
+    fn field(&self, name: &str) -> &Field<'a> {
+        self.fields.iter().flatten().find(|f| f.name == name).unwrap_or_else(|| panic!("Pattern has no field {}", name))
+    }
+}
+
+/// A block of consecutive non-blank lines, remembering the 1-based line number it starts on.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Section {
+    pub first_line: usize,
+    pub lines: Vec<String>,
+}
+
+impl Section {
+    /// The lines of the section with their 1-based line numbers in the whole input.
+    pub fn numbered_lines(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.lines.iter().enumerate().map(move |(i, l)| (self.first_line + i, l.as_str()))
+    }
+}
+
+/// Splits an input into a header, which is its first block of lines, and the blank-line-separated
+/// sections after it, and parses them with `header` and `section`. Any number of blank lines may
+/// separate sections or trail the input.
+pub fn parse_sections<H, S, R, FH, FS>(reader: R, header: FH, mut section: FS) -> Result<(H, Vec<S>)>
+    where R: BufRead,
+          FH: FnOnce(&Section) -> Result<H>,
+          FS: FnMut(&Section) -> Result<S>,
 {
-    let l = line?;
-    l.parse::<U>().map_err(|_| Error::msg(format!("Couldn't parse: {}", l)))
+    let mut sections = split_sections(reader)?.into_iter();
+    let first = sections.next().unwrap_or(Section{first_line: 1, lines: vec![]});
+
+    let header = header(&first)?;
+    let sections = sections.map(|s| section(&s)).collect::<Result<_>>()?;
+
+    Ok((header, sections))
+}
+
+fn split_sections<R: BufRead>(reader: R) -> Result<Vec<Section>> {
+    let mut sections = vec![];
+    let mut current: Option<Section> = None;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            sections.extend(current.take());
+        } else {
+            current.get_or_insert_with(|| Section{first_line: i + 1, lines: vec![]}).lines.push(line);
+        }
+    }
+    sections.extend(current);
+
+    Ok(sections)
+}
+
+/// Like [`parse_line_by_line`], but carries on past lines that don't parse, returning the records
+/// that parsed and an error for each line that didn't. Only failing to read the input stops it.
+pub fn validate_line_by_line<T, R: BufRead>(reader: R) -> Result<(Vec<T>, Vec<PuzzleError>)>
+    where T: FromStr,
+          T::Err: Error + Send + Sync + 'static,
+{
+    let mut records = vec![];
+    let mut errors = vec![];
+
+    for parsed in InputText::read(reader)?.records() {
+        match parsed {
+            Ok(record) => records.push(record),
+            Err(e @ PuzzleError::Parse{..}) => errors.push(e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok((records, errors))
+}
+
+/// Maps the index of a record that parsed leniently back to its line in the input, by stepping
+/// over the lines that were skipped.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct LineNumbers {
+    skipped: Vec<usize>,
+}
+
+impl LineNumbers {
+    pub fn skipping(skipped: &[PuzzleError]) -> Self {
+        let mut skipped: Vec<usize> = skipped.iter().filter_map(PuzzleError::line).collect();
+        skipped.sort_unstable();
+        LineNumbers{skipped}
+    }
+
+    pub fn of(&self, index: usize) -> usize {
+        self.skipped.iter().fold(index + 1, |line, &skipped| if skipped <= line { line + 1 } else { line })
+    }
+}
+
+fn parse_line<U>(line_number: usize, line: &str) -> Result<U>
+    where U: FromStr,
+          U::Err: Error + Send + Sync + 'static,
+{
+    line.parse::<U>().map_err(|e| PuzzleError::parse(Some(line_number), line, e))
+}
+
+const NEIGHBOURS: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const DIAGONALS: [(isize, isize); 4] = [(1, -1), (1, 1), (-1, 1), (-1, -1)];
+
+/// A rectangular grid stored row by row. Cells are addressed as `(x, y)`, with `x` the column and
+/// `y` the row, both counted from the top left.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+        where T: Clone
+    {
+        Grid{width, height, cells: vec![fill; width * height]}
+    }
+
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self> {
+        let width = rows.first().map_or(0, Vec::len);
+        let height = rows.len();
+
+        if let Some(y) = rows.iter().position(|r| r.len() != width) {
+            return Err(PuzzleError::invalid_data(format!("Row {} has {} cells, expected {}", y + 1, rows[y].len(), width)));
+        }
+
+        Ok(Grid{width, height, cells: rows.into_iter().flatten().collect()})
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.offset(x, y).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        self.offset(x, y).map(move |i| &mut self.cells[i])
+    }
+
+    pub fn row(&self, y: usize) -> impl Iterator<Item = &T> {
+        assert!(y < self.height, "Row {} is outside a {}x{} grid", y, self.width, self.height);
+        self.cells[y * self.width..(y + 1) * self.width].iter()
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "Column {} is outside a {}x{} grid", x, self.width, self.height);
+        self.cells.iter().skip(x).step_by(self.width).take(self.height)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1)).take(self.height)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells.iter().enumerate().map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+
+    fn offset(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    fn shifted(&self, x: usize, y: usize, offsets: impl Iterator<Item = &'static (isize, isize)>) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+
+        offsets.filter_map(move |&(dx, dy)| {
+            let nx = x.checked_add_signed(dx).filter(|&nx| nx < width)?;
+            let ny = y.checked_add_signed(dy).filter(|&ny| ny < height)?;
+            Some((nx, ny))
+        })
+    }
+}
+
+// Helpers for the grid puzzles still to come.
+#[allow(dead_code)]
+impl<T> Grid<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The positions up, right, down and left of `(x, y)` that are inside the grid.
+    pub fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.shifted(x, y, NEIGHBOURS.iter())
+    }
+
+    /// Like [`Grid::neighbours`], but including the four diagonal positions.
+    pub fn neighbours_with_diagonals(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.shifted(x, y, NEIGHBOURS.iter().chain(DIAGONALS.iter()))
+    }
+
+    /// Parses a grid with one cell per character, e.g. a map of `#` and `.`.
+    pub fn parse_cells<R, F>(reader: R, cell: F) -> Result<Self>
+        where R: BufRead,
+              F: Fn(char) -> Option<T>,
+    {
+        let mut rows = vec![];
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let row = line.chars().enumerate()
+                .map(|(column, c)| cell(c).ok_or_else(|| {
+                    PuzzleError::parse(Some(i + 1), &line, ParseError::at(column + 1, format!("Unexpected {:?}", c)))
+                }))
+                .collect::<Result<Vec<_>>>()?;
+
+            if rows.first().is_some_and(|first: &Vec<T>| first.len() != row.len()) {
+                return Err(PuzzleError::parse(Some(i + 1), &line, ParseError::new("Row has a different width than the first")));
+            }
+
+            rows.push(row);
+        }
+
+        Grid::from_rows(rows)
+    }
+}
+
+#[allow(dead_code)]
+impl Grid<char> {
+    pub fn parse_chars<R: BufRead>(reader: R) -> Result<Self> {
+        Grid::parse_cells(reader, Some)
+    }
+}
+
+#[allow(dead_code)]
+impl Grid<u8> {
+    pub fn parse_digits<R: BufRead>(reader: R) -> Result<Self> {
+        Grid::parse_cells(reader, |c| c.to_digit(10).map(|d| d as u8))
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y).unwrap_or_else(|| panic!("({}, {}) is outside a {}x{} grid", x, y, self.width, self.height))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        self.get_mut(x, y).unwrap_or_else(|| panic!("({}, {}) is outside a {}x{} grid", x, y, width, height))
+    }
+}
+
+/// One line per row. Cells are written back to back, or right-aligned to the width if one is
+/// given, so `format!("{:3}", grid)` lines up multi-digit numbers.
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                match f.width() {
+                    Some(width) => write!(f, "{:>width$}", cell.to_string(), width = width)?,
+                    None => write!(f, "{}", cell)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::*;
+
+    #[test]
+    fn test_parse_inline_text() {
+        let source = InputSource::Text("1\n2\n3\n".to_string());
+        let parsed: Vec<i32> = parse_line_by_line(source.open().unwrap()).unwrap();
+        assert_eq!(parsed, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_missing_file_mentions_path() {
+        let error = InputSource::File("/nonexistent/1.txt".to_string()).open().err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::MissingInput);
+        assert!(error.to_string().contains("/nonexistent/1.txt"));
+    }
+
+    fn temporary_path(name: &str) -> String {
+        env::temp_dir().join(format!("aoc_2021_input_{}_{}", std::process::id(), name)).display().to_string()
+    }
+
+    #[test]
+    fn test_compressed_inputs() {
+        use std::io::Write;
+
+        let gzip_path = temporary_path("1.txt.gz");
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&gzip_path).unwrap(), flate2::Compression::default());
+        encoder.write_all(b"1\r\n2\n3\n").unwrap();
+        encoder.finish().unwrap();
+
+        // No extension, so only the magic bytes give it away.
+        let zstd_path = temporary_path("1_zstd");
+        std::fs::write(&zstd_path, zstd::encode_all(&b"4\n5\n"[..], 0).unwrap()).unwrap();
+
+        assert_eq!(parse_file_line_by_line::<i32>(&gzip_path).unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_file_line_by_line::<i32>(&zstd_path).unwrap(), vec![4, 5]);
+
+        std::fs::remove_file(&gzip_path).unwrap();
+        std::fs::remove_file(&zstd_path).unwrap();
+    }
+
+    #[test]
+    fn test_detect_compression() {
+        assert_eq!(Compression::detect("5.txt.zst", b""), Compression::Zstd);
+        assert_eq!(Compression::detect("5.txt", &[0x1f, 0x8b, 8]), Compression::Gzip);
+        assert_eq!(Compression::detect("5.txt", b"0,9 -> 5,9"), Compression::None);
+    }
+
+    #[test]
+    fn test_parse_error_has_line_and_cause() {
+        let error = parse_line_by_line::<i32, _>("1\n2\nthree\n".as_bytes()).unwrap_err();
+        assert_eq!(error.chain(), "Line 3: invalid digit found in string in \"three\"");
+    }
+
+    const SEGMENT: Pattern = Pattern::new("{x1},{y1} -> {x2},{y2}");
+
+    #[test]
+    fn test_pattern_fields() {
+        let fields = SEGMENT.captures("0,9 -> 15,9").unwrap();
+
+        assert_eq!(fields.get::<u32>("x2").unwrap(), 15);
+        assert_eq!(fields.text("y1"), "9");
+        assert_eq!(fields.column("x2"), 8);
+        assert_eq!(Pattern::new("{dir} {n}").captures("forward 5").unwrap().text("dir"), "forward");
+    }
+
+    #[test]
+    fn test_pattern_errors() {
+        let message = |line| SEGMENT.captures(line).and_then(|f| f.get::<u32>("y1")).map(|_| ()).unwrap_err();
+
+        assert_eq!(message("0,9 - 5,9").to_string(), "Expected \" -> \" after y1");
+        assert_eq!(message("0,9 - 5,9").column, Some(3));
+        assert_eq!(message("0,x -> 5,9").to_string(), "Invalid y1 \"x\"");
+        assert_eq!(message("0, -> 5,9").to_string(), "Missing y1");
+        assert_eq!(Pattern::new("{n}!").captures("5!?").unwrap_err().to_string(), "Unexpected \"?\"");
+    }
+
+    #[test]
+    fn test_pattern_fields_ignore_surrounding_spaces() {
+        let fields = SEGMENT.captures("0 ,9 ->  5, 9").unwrap();
+        assert_eq!(fields.get::<u32>("x1").unwrap(), 0);
+        assert_eq!((fields.text("x2"), fields.column("x2")), ("5", 10));
+
+        for line in ["0,9->5,9", "0,9 ->5,9", "0,9-> 5,9"] {
+            let fields = SEGMENT.captures(line).unwrap();
+            assert_eq!((fields.text("y1"), fields.text("x2")), ("9", "5"), "{}", line);
+        }
+        assert!(Pattern::new("{dir} {n}").captures("forward5").is_err());
+    }
+
+    #[test]
+    fn test_normalised_input() {
+        let mut text = String::new();
+        Normalised::new("\u{feff}forward\t 5\r\n\r\n  up  3  \r\n\n \n".as_bytes()).read_to_string(&mut text).unwrap();
+        assert_eq!(text, "forward 5\n\nup 3\n");
+    }
+
+    #[test]
+    fn test_normalised_lines_across_reads() {
+        let input = "\u{feff}forward\t 5\r\n\r\n  up  3  \r\n\n \ndown 10";
+        let mut text = String::new();
+        Normalised::new(BufReader::with_capacity(3, input.as_bytes())).read_to_string(&mut text).unwrap();
+        assert_eq!(text, "forward 5\n\nup 3\n\n\ndown 10\n");
+
+        let mut text = String::new();
+        let error = Normalised::new(&b"1\n\xff 2\n"[..]).read_to_string(&mut text).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_normalised_inline_text() {
+        let source = InputSource::Text("1\r\n 2\r\n\r\n".to_string());
+        assert_eq!(parse_line_by_line::<i32, _>(source.open().unwrap()).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_input_text_borrows_lines() {
+        let text = InputText::read(Normalised::new("1\r\n2\nx\n".as_bytes())).unwrap();
+        let records: Vec<Result<u8>> = text.records().collect();
+
+        assert_eq!(text.lines().collect::<Vec<_>>(), vec!["1", "2", "x"]);
+        assert_eq!(records[1].as_ref().unwrap(), &2);
+        assert_eq!(records[2].as_ref().unwrap_err().to_string(), "Line 3: invalid digit found in string in \"x\"");
+    }
+
+    #[test]
+    fn test_parsed_lines_are_lazy() {
+        let mut lines = parsed_lines::<i32, _>("1\nx\n3\n".as_bytes());
+
+        assert_eq!(lines.next().unwrap().unwrap(), 1);
+        assert!(lines.next().unwrap().is_err());
+        assert_eq!(lines.next().unwrap().unwrap(), 3);
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_parse_file_line_by_line() {
+        let path = temporary_path("1_parse_file.txt");
+        std::fs::write(&path, "1\nx\n").unwrap();
+
+        let error = parse_file_line_by_line::<i32>(&path).unwrap_err();
+        assert_eq!(error.to_string(), format!("{}:2: invalid digit found in string in \"x\"", path));
+        assert_eq!(parse_file_line_by_line::<i32>("/nonexistent/1.txt").unwrap_err().kind(), ErrorKind::MissingInput);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_lines_parsed() {
+        let path = temporary_path("1_lines_parsed.txt");
+        std::fs::write(&path, "1\nx\n3\n").unwrap();
+
+        let mut lines = lines_parsed::<i32>(&path);
+        assert_eq!(lines.next().unwrap().unwrap(), 1);
+        assert!(lines.next().unwrap().unwrap_err().to_string().starts_with(&format!("{}:2: ", path)));
+        assert_eq!(lines.next().unwrap().unwrap(), 3);
+        assert!(lines.next().is_none());
+
+        let mut missing = lines_parsed::<i32>("/nonexistent/1.txt");
+        assert_eq!(missing.next().unwrap().unwrap_err().kind(), ErrorKind::MissingInput);
+        assert!(missing.next().is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_validate_collects_every_error() {
+        let (records, errors) = validate_line_by_line::<i32, _>("1\ntwo\n3\nfour\n".as_bytes()).unwrap();
+        let lines: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+
+        assert_eq!(records, vec![1, 3]);
+        assert_eq!(lines, vec!["Line 2: invalid digit found in string in \"two\"",
+                               "Line 4: invalid digit found in string in \"four\""]);
+    }
+
+    #[test]
+    fn test_line_numbers_step_over_skipped_lines() {
+        let (records, errors) = validate_line_by_line::<i32, _>("x\n2\ny\nz\n5\n".as_bytes()).unwrap();
+        let lines = LineNumbers::skipping(&errors);
+
+        assert_eq!(records, vec![2, 5]);
+        assert_eq!((lines.of(0), lines.of(1)), (2, 5));
+        assert_eq!(LineNumbers::default().of(3), 4);
+    }
+
+    #[test]
+    fn test_sections_ignore_extra_blank_lines() {
+        let input = "header\n\n\na\nb\n\nc\n\n\n";
+        let (header, sections) = parse_sections(input.as_bytes(),
+                                                |s| Ok(s.lines.clone()),
+                                                |s| Ok(s.numbered_lines().map(|(n, l)| format!("{}:{}", n, l)).collect::<Vec<_>>()))
+            .unwrap();
+
+        assert_eq!(header, vec!["header"]);
+        assert_eq!(sections, vec![vec!["4:a", "5:b"], vec!["7:c"]]);
+    }
+
+    #[test]
+    fn test_sections_of_empty_input() {
+        let (header, sections) = parse_sections("".as_bytes(), |s| Ok(s.lines.len()), |_| Ok(())).unwrap();
+        assert_eq!((header, sections.len()), (0, 0));
+    }
+
+    #[test]
+    fn test_grid_access_and_iteration() {
+        let grid = Grid::parse_digits("123\n456\n".as_bytes()).unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 1)], 6);
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.row(1).copied().collect::<Vec<_>>(), vec![4, 5, 6]);
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![2, 5]);
+        assert_eq!(grid.to_string(), "123\n456");
+        assert_eq!(format!("{:2}", grid), " 1 2 3\n 4 5 6");
+    }
+
+    #[test]
+    #[should_panic(expected = "Row 2 is outside a 3x2 grid")]
+    fn test_grid_row_out_of_bounds() {
+        Grid::new(3, 2, 0).row(2).count();
+    }
+
+    #[test]
+    #[should_panic(expected = "Column 3 is outside a 3x2 grid")]
+    fn test_grid_column_out_of_bounds() {
+        Grid::new(3, 2, 0).column(3).count();
+    }
+
+    #[test]
+    fn test_grid_neighbours() {
+        let grid = Grid::new(3, 3, 0);
+
+        assert_eq!(grid.neighbours(0, 0).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbours(1, 1).count(), 4);
+        assert_eq!(grid.neighbours_with_diagonals(0, 0).collect::<Vec<_>>(), vec![(1, 0), (0, 1), (1, 1)]);
+        assert_eq!(grid.neighbours_with_diagonals(1, 1).count(), 8);
+    }
+
+    #[test]
+    fn test_grid_parse_errors() {
+        let error = Grid::parse_digits("12\n3x\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "Line 2, column 2: Unexpected 'x' in \"3x\"");
+
+        assert!(Grid::parse_chars("..\n...\n".as_bytes()).is_err());
+        assert!(Grid::from_rows(vec![vec![1, 2], vec![3]]).is_err());
+    }
+
+    #[test]
+    fn test_default_pattern() {
+        let locator = InputLocator::new(Some("/data".to_string()), Some(DEFAULT_INPUT_PATTERN.to_string()));
+        assert_eq!(locator.path_for(4), "/data/4.txt");
+        assert_eq!(locator.example_path_for(4), "/data/4_example.txt");
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let locator = InputLocator::new(Some("in".to_string()), Some("day{day:02}/input".to_string()));
+        assert_eq!(locator.path_for(4), "in/day04/input");
+        assert_eq!(locator.path_for(12), "in/day12/input");
+        assert_eq!(locator.example_path_for(4), "in/day04/input_example");
+    }
+
+    #[test]
+    fn test_example_pattern() {
+        assert_eq!(example_pattern(DEFAULT_INPUT_PATTERN), EXAMPLE_INPUT_PATTERN);
+        assert_eq!(example_pattern("{day:02}.in"), "{day:02}_example.in");
+        assert_eq!(example_pattern("{day}.txt.gz"), "{day}_example.txt.gz");
+        assert_eq!(example_pattern("v1.2/day{day}"), "v1.2/day{day}_example");
+    }
+
+    #[test]
+    fn test_override_for_single_day() {
+        let locator = InputLocator::new(Some("in".to_string()), Some("{day}.txt".to_string()))
+            .with_override(3, "/tmp/three.txt".to_string());
+
+        assert_eq!(locator.path_for(3), "/tmp/three.txt");
+        assert!(locator.is_overridden(3));
+        assert_eq!(locator.path_for(5), "in/5.txt");
+    }
 }
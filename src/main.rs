@@ -1,37 +1,234 @@
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
+#[macro_use]
+mod solution;
+
+mod answers;
+mod cli;
+mod error;
 mod input_handling;
+mod report;
+mod runner;
+mod scaffold;
+mod watch;
+
+use std::io::Read;
+use std::path::Path;
+use std::process::ExitCode;
+use crate::answers::{Answers, DEFAULT_ANSWERS_FILE, EXAMPLE_ANSWERS_FILE};
+use crate::cli::{Command, Options, USAGE};
+use crate::error::PuzzleError;
+use crate::input_handling::{InputLocator, InputSource};
+use crate::report::{format_validation, Failure, Record};
+use crate::runner::{run_in_order, Measurement, ParseMode, Puzzle};
+use crate::watch::watch;
+
+solutions! {
+    day1::SonarSweep,
+    day2::Dive,
+    day3::BinaryDiagnostic,
+    day4::GiantSquid,
+    day5::HydrothermalVenture,
+}
+
+struct Job<'a> {
+    puzzle: &'a Puzzle,
+    parts: Vec<u32>,
+    source: InputSource,
+    verify: bool,
+}
+
+fn main() -> ExitCode {
+    let options = match Options::from_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if options.help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    if let Command::NewDay{day, title} = &options.command {
+        return match scaffold::new_day(Path::new(scaffold::PROJECT_ROOT), *day, title) {
+            Ok(changed) => {
+                changed.iter().for_each(|path| println!("Wrote {}", path.display()));
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let locator = options.input_files.iter()
+        .fold(InputLocator::new(options.input_dir.clone(), options.input_pattern.clone()),
+              |locator, (day, path)| locator.with_override(*day, path.clone()));
+
+    let inline_source = match read_inline_input(&options) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let puzzles = registered_puzzles();
+
+    if !puzzles.iter().any(|p| options.selects(p.day, 1) || options.selects(p.day, 2)) {
+        let solved: Vec<_> = puzzles.iter().map(|p| p.day.to_string()).collect();
+        eprintln!("No solved day matches the selection (solved days: {})", solved.join(", "));
+        return ExitCode::from(2);
+    }
+
+    if let Some((day, _)) = options.input_files.iter().find(|(day, _)| puzzles.iter().all(|p| p.day != *day)) {
+        eprintln!("--input-file given for day {}, which has no solution yet", day);
+        return ExitCode::from(2);
+    }
+
+    if options.command == Command::Validate {
+        return if validate(&options, &puzzles, &locator, &inline_source) { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+    }
+
+    if options.watch {
+        let watched = puzzles.iter()
+            .filter(|p| options.selects(p.day, 1) || options.selects(p.day, 2))
+            .filter(|_| inline_source.is_none())
+            .flat_map(|p| [locator.path_for(p.day), locator.example_path_for(p.day)])
+            .collect();
+
+        let result = watch(watched, || {
+            if let Err(e) = run(&options, &puzzles, &locator, &inline_source) {
+                eprintln!("{:#}", e);
+            }
+        });
+
+        if let Err(e) = result {
+            eprintln!("{:#}", e);
+        }
+        return ExitCode::FAILURE;
+    }
 
-use std::error::Error;
-use crate::input_handling::DEFAULT_INPUT_FOLDER;
-
-fn main() -> Result<(), Box<dyn Error>> {
-    type Exercise = fn(&str) -> anyhow::Result<i32>;
-
-    let exercises = [
-        (1, 1, day1::part1 as Exercise),
-        (1, 2, day1::part2),
-        (2, 1, day2::part1),
-        (2, 2, day2::part2),
-        (3, 1, day3::part1),
-        (3, 2, day3::part2),
-        (4, 1, day4::part1),
-        (4, 2, day4::part2),
-        (5, 1, day5::part1),
-        (5, 2, day5::part2),
-    ];
-
-    for (day, part, fp) in &exercises {
-        let current_path = format!("{}/{}.txt", DEFAULT_INPUT_FOLDER, day);
-
-        match fp(&current_path) {
-            Ok(result) => println!("Day {}, part {}: Result = {}", day, part, result),
-            Err(e) => println!("Day {}, part {}: Failed ({})", day, part, e),
+    match run(&options, &puzzles, &locator, &inline_source) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            ExitCode::from(2)
         }
     }
+}
+
+/// Runs the selected days and prints their records. Returns whether every part succeeded with an
+/// answer that isn't known to be wrong.
+fn run(options: &Options, puzzles: &[Puzzle], locator: &InputLocator, inline_source: &Option<InputSource>) -> anyhow::Result<bool> {
+    let answers_file = if options.example { EXAMPLE_ANSWERS_FILE } else { DEFAULT_ANSWERS_FILE };
+    let answers_path = options.answers.clone()
+        .unwrap_or_else(|| locator.file_in_folder(answers_file));
+    let answers = Answers::load(&answers_path)?;
+    let runs = options.bench.unwrap_or(1);
+    let mode = match (options.lenient, options.stream) {
+        (true, _) => ParseMode::Lenient,
+        (_, true) => ParseMode::Streaming,
+        _ => ParseMode::Strict,
+    };
+
+    let mut failed = false;
+
+    if let Some(header) = options.format.header() {
+        println!("{}", header);
+    }
+
+    let jobs: Vec<Job> = puzzles.iter()
+        .filter_map(|puzzle| {
+            let parts: Vec<u32> = (1..=2).filter(|&part| options.selects(puzzle.day, part)).collect();
+            if parts.is_empty() {
+                return None;
+            }
+
+            let (source, verify) = source_for(options, puzzle.day, locator, inline_source);
+            Some(Job{puzzle, parts, source, verify})
+        })
+        .collect();
+
+    run_in_order(&jobs, options.jobs, |job| job.puzzle.measure(&job.source, mode, &job.parts, runs), |job, result| {
+        let Job{puzzle, parts, verify, ..} = job;
+        let result = result.and_then(|measured| measured);
+
+        let mut skipped = 0;
+        let outcomes: Vec<(u32, Result<Measurement, Failure>)> = match result {
+            Ok(measurement) => {
+                skipped = measurement.skipped.len();
+                if let Some(line) = options.format.format_parse(puzzle.day, puzzle.title, measurement.runs, &measurement.parse).filter(|_| measurement.parsed) {
+                    println!("{}", line);
+                }
+                for skipped in &measurement.skipped {
+                    eprintln!("Day {} ({}): Skipped {}", puzzle.day, puzzle.title, skipped.chain());
+                }
+
+                measurement.parts.into_iter().map(|(part, outcome)| (part, outcome.map_err(|e| Failure::from(&e)))).collect()
+            }
+            Err(e) => parts.iter().map(|&part| (part, Err(Failure::from(&e)))).collect(),
+        };
+
+        for (part, outcome) in outcomes {
+            let verdict = outcome.as_ref().ok()
+                .filter(|_| *verify)
+                .map(|m| answers.check(puzzle.day, part, &m.result.to_string()));
+            let record = Record{day: puzzle.day, part, title: puzzle.title, outcome, verdict, skipped};
+
+            failed |= record.failed();
+            println!("{}", options.format.format(&record));
+        }
+    });
+
+    Ok(!failed)
+}
+
+/// Parses the selected days' inputs without solving them and prints what was found. Returns
+/// whether every input parsed without issues.
+fn validate(options: &Options, puzzles: &[Puzzle], locator: &InputLocator, inline_source: &Option<InputSource>) -> bool {
+    let mut valid = true;
+
+    let jobs: Vec<(&Puzzle, InputSource)> = puzzles.iter()
+        .filter(|puzzle| options.selects(puzzle.day, 1) || options.selects(puzzle.day, 2))
+        .map(|puzzle| (puzzle, source_for(options, puzzle.day, locator, inline_source).0))
+        .collect();
+
+    run_in_order(&jobs, options.jobs, |(puzzle, source)| puzzle.inspect(source), |(puzzle, _), result| {
+        let outcome = result
+            .and_then(|inspected| inspected)
+            .map(|(mut inspection, skipped)| {
+                let parse_issues = skipped.iter().map(PuzzleError::chain);
+                inspection.issues.splice(0..0, parse_issues);
+                inspection
+            })
+            .map_err(|e| Failure::from(&e));
+
+        valid &= outcome.as_ref().is_ok_and(|inspection| inspection.issues.is_empty());
+        println!("{}", format_validation(puzzle.day, puzzle.title, &outcome));
+    });
+
+    valid
+}
+
+/// Where a day's input comes from, and whether its answers can be checked against the answers file.
+fn source_for(options: &Options, day: u32, locator: &InputLocator, inline_source: &Option<InputSource>) -> (InputSource, bool) {
+    match inline_source {
+        Some(source) => (source.clone(), false),
+        None if options.example => (InputSource::File(locator.example_path_for(day)), true),
+        None => (InputSource::File(locator.path_for(day)), !locator.is_overridden(day)),
+    }
+}
+
+fn read_inline_input(options: &Options) -> anyhow::Result<Option<InputSource>> {
+    if options.stdin {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        return Ok(Some(InputSource::Text(text)));
+    }
 
-    Ok(())
+    Ok(options.input_text.clone().map(InputSource::Text))
 }
@@ -0,0 +1,337 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+use anyhow::Error;
+use crate::answers::Verdict;
+use crate::error::{ErrorKind, PuzzleError};
+use crate::runner::{Measurement, Summary};
+use crate::solution::{Answer, Inspection};
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+pub enum Format {
+    #[default]
+    Text,
+    JsonLines,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "text" => Ok(Format::Text),
+            "json" | "jsonl" => Ok(Format::JsonLines),
+            "csv" => Ok(Format::Csv),
+            _ => Err(Error::msg(format!("Unknown output format: {}", s))),
+        }
+    }
+}
+
+/// What's left of a [`PuzzleError`] once it's reported: its category and its full source chain.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Failure {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl From<&PuzzleError> for Failure {
+    fn from(e: &PuzzleError) -> Self {
+        Failure{kind: e.kind(), message: e.chain()}
+    }
+}
+
+/// `skipped` counts the input lines a lenient parse left out of the answer.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub day: u32,
+    pub part: u32,
+    pub title: &'static str,
+    pub outcome: Result<Measurement, Failure>,
+    pub verdict: Option<Verdict>,
+    pub skipped: usize,
+}
+
+impl Record {
+    pub fn status(&self) -> &'static str {
+        match self.outcome {
+            Ok(_) => "ok",
+            Err(_) => "failed",
+        }
+    }
+
+    pub fn failed(&self) -> bool {
+        self.outcome.is_err() || matches!(self.verdict, Some(Verdict::Wrong(_)))
+    }
+}
+
+const CSV_HEADER: &str = "day,part,title,status,answer,verdict,expected,error,error_kind,runs,parse_ns,solve_ns,total_ns,total_min_ns,total_max_ns,skipped";
+
+impl Format {
+    pub fn header(&self) -> Option<&'static str> {
+        match self {
+            Format::Csv => Some(CSV_HEADER),
+            _ => None,
+        }
+    }
+
+    /// A line describing the parse of a day's input, which both parts share. Structured formats
+    /// carry the parse time on each part's record instead.
+    pub fn format_parse(&self, day: u32, title: &str, runs: usize, parse: &Summary) -> Option<String> {
+        match self {
+            Format::Text => Some(format!("Day {} ({}): Parsed input ({})", day, title, describe_summary(runs, parse))),
+            _ => None,
+        }
+    }
+
+    pub fn format(&self, record: &Record) -> String {
+        match self {
+            Format::Text => format_text(record),
+            Format::JsonLines => format_json(record),
+            Format::Csv => format_csv(record),
+        }
+    }
+}
+
+fn format_text(record: &Record) -> String {
+    match &record.outcome {
+        Ok(m) => {
+            let verdict = record.verdict.as_ref().map(|v| format!(" [{}]", v)).unwrap_or_default();
+            format!("Day {}, part {} ({}): Result = {}{} (solve {})",
+                    record.day, record.part, record.title, m.result, verdict, describe_summary(m.runs, &m.solve))
+        }
+        Err(f) => {
+            let hint = match f.kind {
+                ErrorKind::MissingInput => " - download it, or point --input-dir/--input-file at it",
+                _ => "",
+            };
+            format!("Day {}, part {} ({}): Failed [{}] ({}){}",
+                    record.day, record.part, record.title, f.kind.description(), f.message, hint)
+        }
+    }
+}
+
+/// Describes what `validate` found in a day's input: its figures on the first line, followed by
+/// one indented line per issue.
+pub fn format_validation(day: u32, title: &str, outcome: &Result<Inspection, Failure>) -> String {
+    let inspection = match outcome {
+        Ok(inspection) => inspection,
+        Err(f) => return format!("Day {} ({}): Failed [{}] ({})", day, title, f.kind.description(), f.message),
+    };
+
+    let status = match inspection.issues.len() {
+        0 => "ok".to_string(),
+        1 => "1 issue".to_string(),
+        n => format!("{} issues", n),
+    };
+    let figures = inspection.figures.iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut text = format!("Day {} ({}): {}", day, title, status);
+    if !figures.is_empty() {
+        write!(text, " ({})", figures).unwrap();
+    }
+    for issue in &inspection.issues {
+        write!(text, "\n    {}", issue).unwrap();
+    }
+    text
+}
+
+fn describe_summary(runs: usize, summary: &Summary) -> String {
+    if runs == 1 {
+        format!("{:?}", summary.median)
+    } else {
+        format!("min/median/max of {} runs: {:?}/{:?}/{:?}", runs, summary.min, summary.median, summary.max)
+    }
+}
+
+fn format_json(record: &Record) -> String {
+    let mut line = format!("{{\"day\":{},\"part\":{},\"title\":{},\"status\":\"{}\"",
+                           record.day, record.part, json_string(record.title), record.status());
+
+    match &record.outcome {
+        Ok(m) => {
+            write!(line, ",\"answer\":{}", json_answer(&m.result)).unwrap();
+            match &record.verdict {
+                Some(v) => write!(line, ",\"verdict\":\"{}\",\"expected\":{}",
+                                  v.name(), v.expected().map_or("null".to_string(), json_string)).unwrap(),
+                None => line.push_str(",\"verdict\":null,\"expected\":null"),
+            }
+            write!(line, ",\"error\":null,\"error_kind\":null,\"runs\":{}", m.runs).unwrap();
+            write!(line, ",\"parse_ns\":{},\"solve_ns\":{},\"total_ns\":{},\"total_min_ns\":{},\"total_max_ns\":{}",
+                   nanos(m.parse.median), nanos(m.solve.median),
+                   nanos(m.total.median), nanos(m.total.min), nanos(m.total.max)).unwrap();
+        }
+        Err(f) => {
+            write!(line, ",\"answer\":null,\"verdict\":null,\"expected\":null,\"error\":{},\"error_kind\":\"{}\",\"runs\":0",
+                   json_string(&f.message), f.kind.name()).unwrap();
+            line.push_str(",\"parse_ns\":null,\"solve_ns\":null,\"total_ns\":null,\"total_min_ns\":null,\"total_max_ns\":null");
+        }
+    }
+
+    write!(line, ",\"skipped\":{}}}", record.skipped).unwrap();
+    line
+}
+
+fn format_csv(record: &Record) -> String {
+    let prefix = format!("{},{},{},{}", record.day, record.part, csv_field(record.title), record.status());
+
+    let line = match &record.outcome {
+        Ok(m) => format!("{},{},{},{},,,{},{},{},{},{},{}",
+                         prefix, csv_field(&m.result.to_string()),
+                         record.verdict.as_ref().map_or("", Verdict::name),
+                         record.verdict.as_ref().and_then(Verdict::expected).map_or(String::new(), csv_field),
+                         m.runs,
+                         nanos(m.parse.median), nanos(m.solve.median),
+                         nanos(m.total.median), nanos(m.total.min), nanos(m.total.max)),
+        Err(f) => format!("{},,,,{},{},0,,,,,", prefix, csv_field(&f.message), f.kind.name()),
+    };
+
+    format!("{},{}", line, record.skipped)
+}
+
+fn json_answer(answer: &Answer) -> String {
+    if answer.is_numeric() {
+        answer.to_string()
+    } else {
+        json_string(&answer.to_string())
+    }
+}
+
+fn nanos(d: Duration) -> u128 {
+    d.as_nanos()
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runner::Summary;
+    use super::*;
+
+    fn successful_record() -> Record {
+        let summary = |ns| Summary{min: Duration::from_nanos(ns), median: Duration::from_nanos(ns), max: Duration::from_nanos(ns)};
+        let measurement = Measurement{result: Answer::Signed(42), runs: 1, parse: summary(10), solve: summary(20), total: summary(30)};
+
+        Record{day: 3, part: 2, title: "Binary Diagnostic", outcome: Ok(measurement), verdict: Some(Verdict::Wrong("41".to_string())), skipped: 2}
+    }
+
+    fn failed_record() -> Record {
+        let failure = Failure{kind: ErrorKind::Parse, message: "Line 1: Couldn't parse \"a, b\"".to_string()};
+        Record{day: 4, part: 1, title: "Giant Squid", outcome: Err(failure), verdict: None, skipped: 0}
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("json".parse::<Format>().unwrap(), Format::JsonLines);
+        assert_eq!("csv".parse::<Format>().unwrap(), Format::Csv);
+        assert!("xml".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn test_format_json() {
+        assert_eq!(Format::JsonLines.format(&successful_record()),
+                   "{\"day\":3,\"part\":2,\"title\":\"Binary Diagnostic\",\"status\":\"ok\",\"answer\":42,\"verdict\":\"wrong\",\"expected\":\"41\",\"error\":null,\"error_kind\":null,\"runs\":1,\
+                   \"parse_ns\":10,\"solve_ns\":20,\"total_ns\":30,\"total_min_ns\":30,\"total_max_ns\":30,\"skipped\":2}");
+        assert_eq!(Format::JsonLines.format(&failed_record()),
+                   "{\"day\":4,\"part\":1,\"title\":\"Giant Squid\",\"status\":\"failed\",\"answer\":null,\"verdict\":null,\"expected\":null,\"error\":\"Line 1: Couldn't parse \\\"a, b\\\"\",\"error_kind\":\"parse\",\"runs\":0,\
+                   \"parse_ns\":null,\"solve_ns\":null,\"total_ns\":null,\"total_min_ns\":null,\"total_max_ns\":null,\"skipped\":0}");
+    }
+
+    #[test]
+    fn test_format_csv() {
+        assert_eq!(Format::Csv.format(&successful_record()), "3,2,Binary Diagnostic,ok,42,wrong,41,,,1,10,20,30,30,30,2");
+        assert_eq!(Format::Csv.format(&failed_record()), "4,1,Giant Squid,failed,,,,\"Line 1: Couldn't parse \"\"a, b\"\"\",parse,0,,,,,,0");
+        assert_eq!(CSV_HEADER.split(',').count(), Format::Csv.format(&successful_record()).split(',').count());
+    }
+
+    #[test]
+    fn test_format_text() {
+        assert_eq!(Format::Text.format(&successful_record()), "Day 3, part 2 (Binary Diagnostic): Result = 42 [wrong, expected 41] (solve 20ns)");
+        assert_eq!(Format::Text.format(&failed_record()), "Day 4, part 1 (Giant Squid): Failed [parse error] (Line 1: Couldn't parse \"a, b\")");
+    }
+
+    #[test]
+    fn test_missing_input_has_hint() {
+        let mut record = failed_record();
+        record.outcome = Err(Failure{kind: ErrorKind::MissingInput, message: "Couldn't open inputs/4.txt".to_string()});
+
+        assert!(Format::Text.format(&record).contains("Failed [missing input] (Couldn't open inputs/4.txt) - download it"));
+    }
+
+    #[test]
+    fn test_format_parse() {
+        let summary = Summary{min: Duration::from_nanos(1), median: Duration::from_nanos(2), max: Duration::from_nanos(3)};
+
+        assert_eq!(Format::Text.format_parse(4, "Giant Squid", 1, &summary).unwrap(), "Day 4 (Giant Squid): Parsed input (2ns)");
+        assert_eq!(Format::Text.format_parse(4, "Giant Squid", 5, &summary).unwrap(),
+                   "Day 4 (Giant Squid): Parsed input (min/median/max of 5 runs: 1ns/2ns/3ns)");
+        assert_eq!(Format::Csv.format_parse(4, "Giant Squid", 1, &summary), None);
+    }
+
+    #[test]
+    fn test_format_validation() {
+        let mut inspection = Inspection::default();
+        inspection.figure("vent lines", 500);
+        inspection.figure("max coordinate", 989);
+        assert_eq!(format_validation(5, "Hydrothermal Venture", &Ok(inspection.clone())),
+                   "Day 5 (Hydrothermal Venture): ok (vent lines: 500, max coordinate: 989)");
+
+        inspection.issue("Line 3 (1,1 -> 2,3) isn't horizontal, vertical or at 45 degrees");
+        assert_eq!(format_validation(5, "Hydrothermal Venture", &Ok(inspection)),
+                   "Day 5 (Hydrothermal Venture): 1 issue (vent lines: 500, max coordinate: 989)\n    \
+                   Line 3 (1,1 -> 2,3) isn't horizontal, vertical or at 45 degrees");
+
+        let failure = Failure{kind: ErrorKind::MissingInput, message: "Couldn't open inputs/5.txt".to_string()};
+        assert_eq!(format_validation(5, "Hydrothermal Venture", &Err(failure)),
+                   "Day 5 (Hydrothermal Venture): Failed [missing input] (Couldn't open inputs/5.txt)");
+    }
+
+    #[test]
+    fn test_text_answers() {
+        let mut record = successful_record();
+        if let Ok(m) = &mut record.outcome {
+            m.result = Answer::from("PZ,GB");
+        }
+
+        assert!(Format::JsonLines.format(&record).contains("\"answer\":\"PZ,GB\""));
+        assert!(Format::Csv.format(&record).contains(",\"PZ,GB\","));
+    }
+
+    #[test]
+    fn test_wrong_answer_fails_record() {
+        let mut record = successful_record();
+        assert!(record.failed());
+
+        record.verdict = Some(Verdict::Unknown);
+        assert!(!record.failed());
+    }
+}
@@ -0,0 +1,428 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::error::{PuzzleError, Result};
+use crate::input_handling::InputSource;
+use crate::solution::{Answer, Inspection};
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Summary {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Summary {
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        assert!(!samples.is_empty(), "Can't summarise an empty set of samples");
+        samples.sort();
+
+        let middle = samples.len() / 2;
+        let median = if samples.len().is_multiple_of(2) {
+            (samples[middle - 1] + samples[middle]) / 2
+        } else {
+            samples[middle]
+        };
+
+        Summary{min: samples[0], median, max: samples[samples.len() - 1]}
+    }
+}
+
+/// Timings of one part. `parse` is the cost of parsing the day's input, which is shared by both
+/// parts, and `total` is that plus the part's own `solve` time. A streamed part has no parse.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Measurement {
+    pub result: Answer,
+    pub runs: usize,
+    pub parse: Summary,
+    pub solve: Summary,
+    pub total: Summary,
+}
+
+/// Whether a bad line fails the whole day, or is skipped and reported alongside the answers.
+/// `Streaming` is strict, but solves the parts that can be solved a line at a time straight from
+/// the input.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+pub enum ParseMode {
+    #[default]
+    Strict,
+    Lenient,
+    Streaming,
+}
+
+/// `skipped` holds the lines a lenient parse left out, from the first run only. `parsed` is false
+/// when every part was streamed, leaving `parse` at zero.
+pub struct DayMeasurement {
+    pub runs: usize,
+    pub parsed: bool,
+    pub parse: Summary,
+    pub skipped: Vec<PuzzleError>,
+    pub parts: Vec<(u32, Result<Measurement>)>,
+}
+
+struct DayRun {
+    parsed: bool,
+    parse: Duration,
+    skipped: Vec<PuzzleError>,
+    parts: Vec<Result<(Answer, Duration)>>,
+}
+
+type Solver<T> = fn(&T) -> Result<Answer>;
+type TimedDay = Box<dyn Fn(&InputSource, ParseMode, &[u32]) -> Result<DayRun> + Send + Sync>;
+type StreamedPart = Box<dyn Fn(&InputSource) -> Result<Answer> + Send + Sync>;
+type InspectedDay = Box<dyn Fn(&InputSource) -> Result<(Inspection, Vec<PuzzleError>)> + Send + Sync>;
+
+/// A day's puzzle with its parser and part solvers erased behind a closure, so that days with
+/// different input types can sit in the same registry.
+pub struct Puzzle {
+    pub day: u32,
+    pub title: &'static str,
+    run: TimedDay,
+    streamed: [Option<StreamedPart>; 2],
+    inspect: Option<InspectedDay>,
+}
+
+impl Puzzle {
+    pub fn new<T, P>(day: u32, title: &'static str, parse: P, solvers: [Solver<T>; 2]) -> Self
+        where P: Fn(&InputSource, ParseMode) -> Result<(T, Vec<PuzzleError>)> + Send + Sync + 'static,
+              T: 'static,
+    {
+        let run = move |source: &InputSource, mode: ParseMode, parts: &[u32]| {
+            let start = Instant::now();
+            let (input, skipped) = parse(source, mode)?;
+            let parse_time = start.elapsed();
+
+            let parts = parts.iter()
+                .map(|&part| {
+                    let start = Instant::now();
+                    let result = solvers[part as usize - 1](&input)?;
+                    Ok((result, start.elapsed()))
+                })
+                .collect();
+
+            Ok(DayRun{parsed: true, parse: parse_time, skipped, parts})
+        };
+
+        Puzzle{day, title, run: Box::new(run), streamed: [None, None], inspect: None}
+    }
+
+    /// Adds solvers that read the input themselves, used for their part in `Streaming` mode.
+    pub fn with_streaming<S>(self, streamed: [Option<S>; 2]) -> Self
+        where S: Fn(&InputSource) -> Result<Answer> + Send + Sync + 'static,
+    {
+        Puzzle{streamed: streamed.map(|s| s.map(|s| Box::new(s) as StreamedPart)), ..self}
+    }
+
+    pub fn with_inspection<I>(self, inspect: I) -> Self
+        where I: Fn(&InputSource) -> Result<(Inspection, Vec<PuzzleError>)> + Send + Sync + 'static,
+    {
+        Puzzle{inspect: Some(Box::new(inspect)), ..self}
+    }
+
+    /// Parses the input without solving, returning what the day has to say about it and the
+    /// lines that didn't parse.
+    pub fn inspect(&self, source: &InputSource) -> Result<(Inspection, Vec<PuzzleError>)> {
+        match &self.inspect {
+            Some(inspect) => inspect(source),
+            None => Ok((Inspection::default(), vec![])),
+        }
+    }
+
+    /// Parses the input and solves the given parts `runs` times. Fails only if parsing fails;
+    /// a part that fails in any run is reported with that run's error.
+    pub fn measure(&self, source: &InputSource, mode: ParseMode, parts: &[u32], runs: usize) -> Result<DayMeasurement> {
+        let runs = runs.max(1);
+        let mut parse_samples = Vec::with_capacity(runs);
+        let mut skipped = None;
+        let mut parsed = false;
+        let mut solved: Vec<Result<(Option<Answer>, Vec<Duration>)>> = parts.iter()
+            .map(|_| Ok((None, Vec::with_capacity(runs))))
+            .collect();
+
+        for _ in 0..runs {
+            let run = self.run_once(source, mode, parts)?;
+            parse_samples.push(run.parse);
+            parsed = run.parsed;
+            skipped.get_or_insert(run.skipped);
+
+            for (accumulated, outcome) in solved.iter_mut().zip(run.parts) {
+                match outcome {
+                    Ok((result, solve_time)) => if let Ok((answer, samples)) = accumulated {
+                        *answer = Some(result);
+                        samples.push(solve_time);
+                    },
+                    Err(e) => if accumulated.is_ok() {
+                        *accumulated = Err(e);
+                    },
+                }
+            }
+        }
+
+        let parse = Summary::from_samples(parse_samples.clone());
+        let parts = parts.iter().zip(solved)
+            .map(|(&part, accumulated)| {
+                let measurement = accumulated.map(|(answer, solve_samples)| {
+                    let part_parse_samples = if self.is_streamed(part, mode) { vec![Duration::ZERO; runs] } else { parse_samples.clone() };
+                    let total_samples = part_parse_samples.iter().zip(&solve_samples).map(|(p, s)| *p + *s).collect();
+                    Measurement{
+                        result: answer.expect("At least one run"),
+                        runs,
+                        parse: Summary::from_samples(part_parse_samples),
+                        solve: Summary::from_samples(solve_samples),
+                        total: Summary::from_samples(total_samples),
+                    }
+                });
+                (part, measurement)
+            })
+            .collect();
+
+        Ok(DayMeasurement{runs, parsed, parse, skipped: skipped.unwrap_or_default(), parts})
+    }
+
+    /// In `Streaming` mode, parts with a streamed solver read the input themselves, and only the
+    /// rest share a parse. A streamed part's solve time includes reading the input.
+    fn run_once(&self, source: &InputSource, mode: ParseMode, parts: &[u32]) -> Result<DayRun> {
+        let streamed = |part: u32| self.streamed[part as usize - 1].as_ref().filter(|_| mode == ParseMode::Streaming);
+        let parsed_parts: Vec<u32> = parts.iter().copied().filter(|&part| !self.is_streamed(part, mode)).collect();
+
+        let mut run = if parsed_parts.is_empty() {
+            DayRun{parsed: false, parse: Duration::ZERO, skipped: vec![], parts: vec![]}
+        } else {
+            (self.run)(source, mode, &parsed_parts)?
+        };
+
+        let mut parsed = run.parts.into_iter();
+        run.parts = parts.iter()
+            .map(|&part| match streamed(part) {
+                Some(solve) => {
+                    let start = Instant::now();
+                    let result = solve(source)?;
+                    Ok((result, start.elapsed()))
+                }
+                None => parsed.next().expect("A result for every parsed part"),
+            })
+            .collect();
+
+        Ok(run)
+    }
+
+    fn is_streamed(&self, part: u32, mode: ParseMode) -> bool {
+        mode == ParseMode::Streaming && self.streamed[part as usize - 1].is_some()
+    }
+}
+
+/// Runs `work` on every item using up to `threads` threads, and hands each result to `done` on the
+/// calling thread in the order of `items`, as soon as it and every result before it are ready.
+/// If `work` panics on an item, that item's result is a [`PuzzleError::Panicked`].
+pub fn run_in_order<I, R, W, D>(items: &[I], threads: usize, work: W, mut done: D)
+    where I: Sync,
+          R: Send,
+          W: Fn(&I) -> R + Sync,
+          D: FnMut(&I, Result<R>),
+{
+    let work = |item: &I| panic::catch_unwind(AssertUnwindSafe(|| work(item)))
+        .map_err(|payload| PuzzleError::Panicked(panic_message(payload)));
+
+    let threads = threads.clamp(1, items.len().max(1));
+    if threads == 1 {
+        items.iter().for_each(|item| done(item, work(item)));
+        return;
+    }
+
+    let next_item = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let (next_item, work) = (&next_item, &work);
+
+            scope.spawn(move || loop {
+                let index = next_item.fetch_add(1, Ordering::Relaxed);
+                if index >= items.len() || sender.send((index, work(&items[index]))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_to_report = 0;
+
+        for (index, result) in receiver {
+            pending.insert(index, result);
+
+            while let Some(result) = pending.remove(&next_to_report) {
+                done(&items[next_to_report], result);
+                next_to_report += 1;
+            }
+        }
+    });
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().map_or("unknown cause", |s| s).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+
+    fn text(s: &str) -> InputSource {
+        InputSource::Text(s.to_string())
+    }
+
+    fn length_puzzle() -> Puzzle {
+        Puzzle::new(1, "Test", |s: &InputSource, _| Ok((s.to_string().len(), vec![])),
+                    [|n: &usize| Ok((n * 2).into()), |_: &usize| Err(PuzzleError::no_solution("boom"))])
+    }
+
+    #[test]
+    fn test_summary_odd_samples() {
+        let samples = vec![Duration::from_millis(5), Duration::from_millis(1), Duration::from_millis(3)];
+        let expected = Summary{
+            min: Duration::from_millis(1),
+            median: Duration::from_millis(3),
+            max: Duration::from_millis(5),
+        };
+
+        assert_eq!(Summary::from_samples(samples), expected);
+    }
+
+    #[test]
+    fn test_summary_even_samples() {
+        let samples = vec![Duration::from_millis(4), Duration::from_millis(1), Duration::from_millis(2), Duration::from_millis(8)];
+        assert_eq!(Summary::from_samples(samples).median, Duration::from_millis(3));
+    }
+
+    #[test]
+    fn test_measure_repeats_day() {
+        let measurement = length_puzzle().measure(&InputSource::File("abc".to_string()), ParseMode::Strict, &[1], 3).unwrap();
+        let (part, outcome) = &measurement.parts[0];
+        let outcome = outcome.as_ref().unwrap();
+
+        assert_eq!(measurement.runs, 3);
+        assert_eq!(*part, 1);
+        assert_eq!(outcome.result, Answer::Unsigned(6));
+        assert!(outcome.total.min >= outcome.solve.min);
+    }
+
+    #[test]
+    fn test_failing_part_doesnt_fail_the_other() {
+        let measurement = length_puzzle().measure(&text(""), ParseMode::Strict, &[1, 2], 2).unwrap();
+        assert!(measurement.parts[0].1.is_ok());
+        assert!(measurement.parts[1].1.is_err());
+    }
+
+    #[test]
+    fn test_parse_once_for_both_parts() {
+        let parses = Arc::new(AtomicUsize::new(0));
+        let counter = parses.clone();
+        let puzzle = Puzzle::new(1, "Test", move |_: &InputSource, _| { counter.fetch_add(1, Ordering::Relaxed); Ok(((), vec![])) },
+                                 [|_: &()| Ok(1.into()), |_: &()| Ok(2.into())]);
+
+        puzzle.measure(&text(""), ParseMode::Strict, &[1, 2], 1).unwrap();
+        assert_eq!(parses.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_parse_error_fails_the_day() {
+        let puzzle = Puzzle::new(1, "Test", |_: &InputSource, _| Err::<((), _), _>(PuzzleError::invalid_data("bad input")),
+                                 [|_: &()| Ok(1.into()), |_: &()| Ok(2.into())]);
+        assert!(puzzle.measure(&text(""), ParseMode::Strict, &[1, 2], 1).is_err());
+    }
+
+    #[test]
+    fn test_skipped_lines_are_kept_once() {
+        let puzzle = Puzzle::new(1, "Test", |_: &InputSource, mode| match mode {
+            ParseMode::Strict | ParseMode::Streaming => Err(PuzzleError::invalid_data("bad line")),
+            ParseMode::Lenient => Ok(((), vec![PuzzleError::invalid_data("bad line")])),
+        }, [|_: &()| Ok(1.into()), |_: &()| Ok(2.into())]);
+
+        let measurement = puzzle.measure(&text(""), ParseMode::Lenient, &[1], 3).unwrap();
+        assert_eq!(measurement.skipped.len(), 1);
+        assert!(measurement.parts[0].1.is_ok());
+        assert!(puzzle.measure(&text(""), ParseMode::Strict, &[1], 1).is_err());
+    }
+
+    #[test]
+    fn test_streaming_solves_without_parsing() {
+        let parses = Arc::new(AtomicUsize::new(0));
+        let counter = parses.clone();
+        let puzzle = Puzzle::new(1, "Test", move |_: &InputSource, _| { counter.fetch_add(1, Ordering::Relaxed); Ok(((), vec![])) },
+                                 [|_: &()| Ok(1.into()), |_: &()| Ok(2.into())])
+            .with_streaming([Some(|_: &InputSource| Ok(10.into())), None]);
+
+        let measurement = puzzle.measure(&text(""), ParseMode::Streaming, &[1], 1).unwrap();
+        assert_eq!(measurement.parts[0].1.as_ref().unwrap().result, Answer::Signed(10));
+        assert_eq!(parses.load(Ordering::Relaxed), 0);
+        assert!(!measurement.parsed);
+
+        let measurement = puzzle.measure(&text(""), ParseMode::Streaming, &[1, 2], 1).unwrap();
+        let results: Vec<_> = measurement.parts.iter().map(|(_, m)| m.as_ref().unwrap().result.clone()).collect();
+        assert_eq!(results, vec![Answer::Signed(10), Answer::Signed(2)]);
+        assert_eq!(parses.load(Ordering::Relaxed), 1);
+
+        let measurement = puzzle.measure(&text(""), ParseMode::Strict, &[1], 1).unwrap();
+        assert_eq!(measurement.parts[0].1.as_ref().unwrap().result, Answer::Signed(1));
+    }
+
+    #[test]
+    fn test_streamed_part_has_no_parse_time() {
+        let puzzle = Puzzle::new(1, "Test", |_: &InputSource, _| { thread::sleep(Duration::from_millis(5)); Ok(((), vec![])) },
+                                 [|_: &()| Ok(1.into()), |_: &()| Ok(2.into())])
+            .with_streaming([Some(|_: &InputSource| Ok(10.into())), None]);
+
+        let measurement = puzzle.measure(&text(""), ParseMode::Streaming, &[1, 2], 2).unwrap();
+        let streamed = measurement.parts[0].1.as_ref().unwrap();
+        let parsed = measurement.parts[1].1.as_ref().unwrap();
+
+        assert_eq!(streamed.parse.max, Duration::ZERO);
+        assert_eq!(streamed.total, streamed.solve);
+        assert!(parsed.parse.min >= Duration::from_millis(5));
+        assert_eq!(parsed.parse, measurement.parse);
+    }
+
+    #[test]
+    fn test_run_in_order_keeps_item_order() {
+        let items: Vec<u64> = (0..20).collect();
+        let mut reported = vec![];
+
+        run_in_order(&items, 4, |&i| {
+            thread::sleep(Duration::from_millis((20 - i) % 7));
+            i * i
+        }, |&i, square| reported.push((i, square.unwrap())));
+
+        assert_eq!(reported, items.iter().map(|&i| (i, i * i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_run_in_order_single_thread() {
+        let mut reported = vec![];
+        run_in_order(&[3, 1, 2], 1, |&i| i + 1, |_, r| reported.push(r.unwrap()));
+        assert_eq!(reported, vec![4, 2, 3]);
+    }
+
+    #[test]
+    fn test_run_in_order_reports_panics_in_place() {
+        for threads in [1, 4] {
+            let mut reported = vec![];
+            run_in_order(&[1, 2, 3, 4], threads, |&i| {
+                assert_ne!(i, 2, "two is right out");
+                i
+            }, |_, r| reported.push(r.map_err(|e| e.to_string())));
+
+            assert_eq!(reported[0], Ok(1));
+            assert!(reported[1].as_ref().unwrap_err().contains("Panicked: assertion `left != right` failed: two is right out"));
+            assert_eq!(reported[2..], [Ok(3), Ok(4)]);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Error, Result};
+use crate::input_handling::{DEFAULT_INPUT_PATTERN, EXAMPLE_INPUT_PATTERN};
+
+pub const PROJECT_ROOT: &str = env!("CARGO_MANIFEST_DIR");
+
+const REGISTRY_START: &str = "solutions! {";
+
+const DAY_TEMPLATE: &str = r#"use std::io::BufRead;
+use crate::error::{PuzzleError, Result};
+use crate::input_handling::parse_line_by_line;
+use crate::solution::{Answer, Solution};
+
+pub struct {name};
+
+impl Solution for {name} {
+    const DAY: u32 = {day};
+    const TITLE: &'static str = {title};
+
+    type Input = Vec<String>;
+
+    fn parse<R: BufRead>(reader: R) -> Result<Self::Input> {
+        parse_line_by_line(reader)
+    }
+
+    fn part1(_input: &Self::Input) -> Result<Answer> {
+        Err(PuzzleError::no_solution("Part 1 isn't solved yet"))
+    }
+
+    fn part2(_input: &Self::Input) -> Result<Answer> {
+        Err(PuzzleError::no_solution("Part 2 isn't solved yet"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "";
+
+    fn create_example_input() -> <{name} as Solution>::Input {
+        {name}::parse(EXAMPLE.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_input() {
+        assert!(!create_example_input().is_empty(), "Paste the example into EXAMPLE");
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!({name}::part1(&create_example_input()).unwrap(), Answer::from("expected example answer"));
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!({name}::part2(&create_example_input()).unwrap(), Answer::from("expected example answer"));
+    }
+}
+"#;
+
+/// Creates `src/day{N}.rs` from the template, empty input and example files, and registers the
+/// new module in `main.rs`. Returns the files that were created or changed.
+pub fn new_day(root: &Path, day: u32, title: &str) -> Result<Vec<PathBuf>> {
+    let name = solution_name(day, title);
+    let module = format!("day{}", day);
+
+    let main_path = root.join("src").join("main.rs");
+    let main = fs::read_to_string(&main_path)
+        .with_context(|| format!("Couldn't read {}", main_path.display()))?;
+    let registered_main = register(&main, &module, &name)?;
+
+    let module_path = root.join("src").join(format!("{}.rs", module));
+    create_new(&module_path, &render_day(&name, day, title))?;
+    fs::write(&main_path, registered_main)?;
+
+    let mut changed = vec![module_path, main_path];
+
+    for pattern in &[DEFAULT_INPUT_PATTERN, EXAMPLE_INPUT_PATTERN] {
+        let input_path = root.join("inputs").join(pattern.replace("{day}", &day.to_string()));
+        match create_new(&input_path, "") {
+            Ok(()) => changed.push(input_path),
+            Err(e) if input_path.exists() => eprintln!("Keeping existing {} ({})", input_path.display(), e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(changed)
+}
+
+/// The day's source, with the title written as an escaped string literal.
+fn render_day(name: &str, day: u32, title: &str) -> String {
+    DAY_TEMPLATE
+        .replace("{name}", name)
+        .replace("{day}", &day.to_string())
+        .replace("{title}", &format!("{:?}", title))
+}
+
+fn create_new(path: &Path, content: &str) -> Result<()> {
+    use std::io::Write;
+
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)
+        .map_err(|e| match e.kind() {
+            ErrorKind::AlreadyExists => Error::msg(format!("{} already exists", path.display())),
+            _ => Error::from(e).context(format!("Couldn't create {}", path.display())),
+        })?;
+
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+fn solution_name(day: u32, title: &str) -> String {
+    let name: String = title.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect();
+
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        _ => format!("Day{}", day),
+    }
+}
+
+/// Adds `module::name` to the `solutions!` invocation, keeping the entries ordered by day.
+fn register(main: &str, module: &str, name: &str) -> Result<String> {
+    let start = main.find(REGISTRY_START)
+        .ok_or_else(|| Error::msg("Couldn't find the solutions! registry in main.rs"))? + REGISTRY_START.len();
+    let end = start + main[start..].find('}')
+        .ok_or_else(|| Error::msg("The solutions! registry in main.rs isn't closed"))?;
+
+    let mut entries: Vec<String> = main[start..end].split(',')
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty())
+        .collect();
+
+    if entries.iter().any(|e| e.split("::").next() == Some(module)) {
+        return Err(Error::msg(format!("{} is already registered", module)));
+    }
+
+    entries.push(format!("{}::{}", module, name));
+    entries.sort_by_key(|e| e.trim_start_matches("day").split("::").next().and_then(|d| d.parse::<u32>().ok()));
+
+    let registry: String = entries.iter().map(|e| format!("\n    {},", e)).collect();
+    Ok(format!("{}{}\n{}", &main[..start], registry, &main[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use super::*;
+
+    const MAIN: &str = "mod cli;\n\nsolutions! {\n    day1::SonarSweep,\n    day10::SyntaxScoring,\n}\n\nfn main() {}\n";
+
+    #[test]
+    fn test_solution_name() {
+        assert_eq!(solution_name(6, "Lanternfish"), "Lanternfish");
+        assert_eq!(solution_name(7, "The Treachery of Whales"), "TheTreacheryOfWhales");
+        assert_eq!(solution_name(8, ""), "Day8");
+        assert_eq!(solution_name(9, "42"), "Day9");
+    }
+
+    #[test]
+    fn test_title_is_escaped() {
+        let source = render_day("AB", 6, r#"a\b "c""#);
+        assert!(source.contains(r#"const TITLE: &'static str = "a\\b \"c\"";"#));
+    }
+
+    #[test]
+    fn test_register_keeps_day_order() {
+        let registered = register(MAIN, "day6", "Lanternfish").unwrap();
+        assert_eq!(registered, "mod cli;\n\nsolutions! {\n    day1::SonarSweep,\n    day6::Lanternfish,\n    day10::SyntaxScoring,\n}\n\nfn main() {}\n");
+    }
+
+    #[test]
+    fn test_register_twice_fails() {
+        assert!(register(MAIN, "day1", "SonarSweep").is_err());
+    }
+
+    #[test]
+    fn test_new_day_creates_files() {
+        let root = env::temp_dir().join(format!("aoc_2021_scaffold_{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("inputs")).unwrap();
+        fs::write(root.join("src/main.rs"), MAIN).unwrap();
+
+        let changed = new_day(&root, 6, "Lanternfish").unwrap();
+        assert_eq!(changed.len(), 4);
+
+        let source = fs::read_to_string(root.join("src/day6.rs")).unwrap();
+        assert!(source.contains("impl Solution for Lanternfish"));
+        assert!(source.contains("const DAY: u32 = 6;"));
+        assert!(fs::read_to_string(root.join("src/main.rs")).unwrap().contains("day6::Lanternfish,"));
+        assert!(root.join("inputs/6_example.txt").exists());
+
+        assert!(new_day(&root, 6, "Lanternfish").is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt::{Display, Formatter};
+use std::io::BufRead;
+use crate::error::{PuzzleError, Result};
+use crate::input_handling::{InputSource, LineNumbers};
+use crate::runner::{ParseMode, Puzzle};
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Answer {
+    Signed(i64),
+    Unsigned(u64),
+    Wide(u128),
+    Text(String),
+}
+
+impl Answer {
+    pub fn is_numeric(&self) -> bool {
+        !matches!(self, Answer::Text(_))
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Signed(n) => write!(f, "{}", n),
+            Answer::Unsigned(n) => write!(f, "{}", n),
+            Answer::Wide(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! answer_from {
+    ($variant:ident: $($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(value: $t) -> Self {
+                Answer::$variant(value.into())
+            }
+        })*
+    };
+}
+
+answer_from!(Signed: i32, i64);
+answer_from!(Unsigned: u32, u64);
+answer_from!(Wide: u128);
+answer_from!(Text: String, &str);
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::Unsigned(value as u64)
+    }
+}
+
+/// What `validate` reports about a parsed input: figures that describe it, and issues that would
+/// trip up the solvers.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Inspection {
+    pub figures: Vec<(&'static str, String)>,
+    pub issues: Vec<String>,
+}
+
+impl Inspection {
+    pub fn figure<V: Display>(&mut self, name: &'static str, value: V) {
+        self.figures.push((name, value.to_string()));
+    }
+
+    pub fn issue<S: Into<String>>(&mut self, issue: S) {
+        self.issues.push(issue.into());
+    }
+}
+
+/// Solves a part from the raw input, reading it a line at a time.
+pub type StreamedSolver = fn(&mut dyn BufRead) -> Result<Answer>;
+
+/// A single day's puzzle: how to parse its input and how to solve both parts from the parsed value.
+pub trait Solution {
+    const DAY: u32;
+    const TITLE: &'static str;
+
+    type Input: 'static;
+
+    fn parse<R: BufRead>(reader: R) -> Result<Self::Input>;
+
+    /// Parses what it can, returning the input built from the lines that parsed with an error for
+    /// each line that didn't. Days whose input isn't one record per line keep this default, which
+    /// is as strict as `parse`.
+    fn parse_leniently<R: BufRead>(reader: R) -> Result<(Self::Input, Vec<PuzzleError>)> {
+        Self::parse(reader).map(|input| (input, vec![]))
+    }
+
+    /// Describes a parsed input for `validate`. `lines` gives the input line of each record, as
+    /// lines that didn't parse are left out. Days that don't override it are only checked for
+    /// parse errors.
+    fn inspect(_input: &Self::Input, _lines: &LineNumbers) -> Inspection {
+        Inspection::default()
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer>;
+    fn part2(input: &Self::Input) -> Result<Answer>;
+
+    /// A solver for `part` that reads the input itself, for `--stream`. Parts without one are
+    /// parsed and solved as usual.
+    fn streamed_solver(_part: u32) -> Option<StreamedSolver> {
+        None
+    }
+}
+
+pub fn puzzle<S: Solution + 'static>() -> Puzzle {
+    Puzzle::new(S::DAY, S::TITLE, parse_source::<S>, [S::part1, S::part2])
+        .with_inspection(inspect_source::<S>)
+        .with_streaming([1, 2].map(|part| S::streamed_solver(part).map(streamed_source)))
+}
+
+fn streamed_source(solve: StreamedSolver) -> impl Fn(&InputSource) -> Result<Answer> {
+    move |source| {
+        let path = source.to_string();
+        solve(&mut source.open()?).map_err(|e| e.in_file(path))
+    }
+}
+
+/// Parses leniently, so every bad line is reported, and inspects whatever did parse.
+fn inspect_source<S: Solution>(source: &InputSource) -> Result<(Inspection, Vec<PuzzleError>)> {
+    parse_source::<S>(source, ParseMode::Lenient).map(|(input, skipped)| (S::inspect(&input, &LineNumbers::skipping(&skipped)), skipped))
+}
+
+fn parse_source<S: Solution>(source: &InputSource, mode: ParseMode) -> Result<(S::Input, Vec<PuzzleError>)> {
+    let path = source.to_string();
+    let parsed = match mode {
+        ParseMode::Strict | ParseMode::Streaming => S::parse(source.open()?).map(|input| (input, vec![])),
+        ParseMode::Lenient => S::parse_leniently(source.open()?),
+    };
+
+    parsed
+        .map(|(input, errors)| (input, errors.into_iter().map(|e| e.in_file(&path)).collect()))
+        .map_err(|e| e.in_file(path))
+}
+
+/// Declares the day modules and builds the registry of their puzzles, so a new day only needs
+/// one `module::Solution` entry here.
+macro_rules! solutions {
+    ($($module:ident :: $solution:ident),* $(,)?) => {
+        $(mod $module;)*
+
+        fn registered_puzzles() -> Vec<$crate::runner::Puzzle> {
+            let mut puzzles = vec![$($crate::solution::puzzle::<$module::$solution>()),*];
+            puzzles.sort_by_key(|p| p.day);
+            puzzles
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registered_puzzles;
+    use super::*;
+
+    #[test]
+    fn test_answer_conversions() {
+        assert_eq!(Answer::from(-3), Answer::Signed(-3));
+        assert_eq!(Answer::from(7usize), Answer::Unsigned(7));
+        assert_eq!(Answer::from(u128::MAX).to_string(), "340282366920938463463374607431768211455");
+        assert_eq!(Answer::from("FGHJ"), Answer::Text("FGHJ".to_string()));
+        assert!(!Answer::from("FGHJ").is_numeric());
+    }
+
+    #[test]
+    fn test_registry_has_every_day_in_order() {
+        let days: Vec<_> = registered_puzzles().iter().map(|p| p.day).collect();
+        let expected: Vec<_> = (1..=days.len() as u32).collect();
+
+        assert_eq!(days, expected);
+    }
+}
@@ -0,0 +1,142 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use anyhow::{Context, Error, Result};
+
+pub const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Remembers the modification time of a set of files, so that polling can tell which of them
+/// changed, appeared or disappeared since the previous poll.
+pub struct Watcher {
+    stamps: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Watcher {
+    pub fn new<P: Into<PathBuf>>(paths: Vec<P>) -> Self {
+        let stamps = paths.into_iter()
+            .map(|p| {
+                let path = p.into();
+                let stamp = modified(&path);
+                (path, stamp)
+            })
+            .collect();
+
+        Watcher{stamps}
+    }
+
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = vec![];
+
+        for (path, stamp) in self.stamps.iter_mut() {
+            let current = modified(path);
+            if current != *stamp {
+                *stamp = current;
+                changed.push(path.clone());
+            }
+        }
+
+        changed
+    }
+
+    fn settle(&mut self) {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            if self.poll().is_empty() {
+                break;
+            }
+        }
+    }
+}
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Calls `run`, then again every time one of `inputs` changes. When the running binary itself is
+/// rebuilt, it is restarted with the same arguments so the new code is picked up.
+pub fn watch<F: FnMut()>(inputs: Vec<String>, mut run: F) -> Result<()> {
+    let executable = env::current_exe().context("Couldn't find the running binary")?;
+    let mut binary = Watcher::new(vec![executable.clone()]);
+    let mut inputs = Watcher::new(inputs);
+
+    run();
+    println!("Watching for changes...");
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        if !binary.poll().is_empty() {
+            binary.settle();
+            if executable.exists() {
+                println!("Binary rebuilt, restarting");
+                return restart(executable);
+            }
+        }
+
+        let changed = inputs.poll();
+        if !changed.is_empty() {
+            inputs.settle();
+
+            let names: Vec<_> = changed.iter().map(|p| p.display().to_string()).collect();
+            println!("Changed: {}", names.join(", "));
+            run();
+            println!("Watching for changes...");
+        }
+    }
+}
+
+#[cfg(unix)]
+fn restart(executable: PathBuf) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    let error = Command::new(&executable).args(env::args_os().skip(1)).exec();
+    Err(Error::from(error).context(format!("Couldn't restart {}", executable.display())))
+}
+
+#[cfg(not(unix))]
+fn restart(executable: PathBuf) -> Result<()> {
+    let status = Command::new(&executable).args(env::args_os().skip(1)).status()
+        .with_context(|| format!("Couldn't restart {}", executable.display()))?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use super::*;
+
+    fn temporary_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("aoc_2021_watch_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_poll_detects_modification() {
+        let path = temporary_path("modified");
+        fs::write(&path, "1").unwrap();
+
+        let mut watcher = Watcher::new(vec![path.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        assert_eq!(watcher.poll(), vec![path.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_poll_detects_creation_and_removal() {
+        let path = temporary_path("created");
+        let mut watcher = Watcher::new(vec![path.clone()]);
+
+        fs::write(&path, "1").unwrap();
+        assert_eq!(watcher.poll(), vec![path.clone()]);
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.poll(), vec![path]);
+    }
+}